@@ -0,0 +1,270 @@
+use std::{cell::RefCell, error::Error, fmt, rc::Rc, time::Instant};
+
+use crate::{
+    node::NodeId, observer::SimulationObserver, CheckSchedule, Configuration, ConfigurationError,
+    DeterministicClient, DeterministicNode, FailureConfiguration, FaultScript, InvariantChecker,
+    NetworkConfig, Rule, Simulator,
+};
+
+/// Error when creating a simulation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BuildError {
+    InvalidConfiguration(ConfigurationError),
+    /// Node ids must be `Node(0)`, `Node(1)`, ... in the order of the nodes.
+    NonSequentialNodeIds(Vec<NodeId>),
+    /// Client ids must be `Client(0)`, `Client(1)`, ... in the order of the
+    /// clients.
+    NonSequentialClientIds(Vec<NodeId>),
+    MissingInvariantChecker,
+    /// The schedule of the named invariant checker checks every 0 events or
+    /// every 0 seconds.
+    InvalidCheckSchedule(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidConfiguration(error) => write!(f, "{}", error),
+            BuildError::NonSequentialNodeIds(ids) => write!(
+                f,
+                "Node IDs must be sequential starting from 0, got {:?}",
+                ids
+            ),
+            BuildError::NonSequentialClientIds(ids) => write!(
+                f,
+                "Client IDs must be sequential starting from 0, got {:?}",
+                ids
+            ),
+            BuildError::MissingInvariantChecker => write!(f, "No invariant checker given"),
+            BuildError::InvalidCheckSchedule(name) => {
+                write!(f, "Invalid schedule for invariant checker '{}'", name)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::InvalidConfiguration(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigurationError> for BuildError {
+    fn from(error: ConfigurationError) -> Self {
+        BuildError::InvalidConfiguration(error)
+    }
+}
+
+// Adds something to the simulation once it is created, via the public setters.
+type Setup<N, C, I> = Box<dyn FnOnce(&mut Simulator<N, C, I>)>;
+
+// A named invariant checker, added once the schedules are validated.
+struct Checker<
+    N: DeterministicNode,
+    C: DeterministicClient<Message = N::Message>,
+    I: InvariantChecker<N, C>,
+> {
+    name: String,
+    schedule: CheckSchedule,
+    add: Setup<N, C, I>,
+}
+
+/// Collects everything a simulation consists of and creates it with
+/// [`Self::build`]. Unless given, the simulation starts now and uses the
+/// default configuration.
+pub struct SimulatorBuilder<
+    N: DeterministicNode,
+    C: DeterministicClient<Message = N::Message>,
+    I: InvariantChecker<N, C>,
+> {
+    start_time: Option<Instant>,
+    nodes: Vec<N>,
+    clients: Vec<C>,
+    config: Configuration,
+    invariant_checker: Option<I>,
+    checkers: Vec<Checker<N, C, I>>,
+    observers: Vec<Rc<RefCell<dyn SimulationObserver<N::Message>>>>,
+    rules: Vec<Rule<N::Message>>,
+    fault_scripts: Vec<FaultScript<N::Message>>,
+    node_factory: Option<Setup<N, C, I>>,
+    record_trace: bool,
+}
+
+impl<
+        N: DeterministicNode,
+        C: DeterministicClient<Message = N::Message>,
+        I: InvariantChecker<N, C>,
+    > Default for SimulatorBuilder<N, C, I>
+{
+    fn default() -> Self {
+        SimulatorBuilder {
+            start_time: None,
+            nodes: vec![],
+            clients: vec![],
+            config: Configuration::default(),
+            invariant_checker: None,
+            checkers: vec![],
+            observers: vec![],
+            rules: vec![],
+            fault_scripts: vec![],
+            node_factory: None,
+            record_trace: false,
+        }
+    }
+}
+
+impl<
+        N: DeterministicNode,
+        C: DeterministicClient<Message = N::Message>,
+        I: InvariantChecker<N, C>,
+    > SimulatorBuilder<N, C, I>
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_time(mut self, start_time: Instant) -> Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    pub fn node(mut self, node: N) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: impl IntoIterator<Item = N>) -> Self {
+        self.nodes.extend(nodes);
+        self
+    }
+
+    pub fn client(mut self, client: C) -> Self {
+        self.clients.push(client);
+        self
+    }
+
+    pub fn clients(mut self, clients: impl IntoIterator<Item = C>) -> Self {
+        self.clients.extend(clients);
+        self
+    }
+
+    /// Replaces the whole configuration, e.g., with a preset.
+    pub fn config(mut self, config: Configuration) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn network_config(mut self, network_config: NetworkConfig) -> Self {
+        self.config.network_config = network_config;
+        self
+    }
+
+    pub fn failure_config(mut self, failure_config: FailureConfiguration) -> Self {
+        self.config.failure_config = failure_config;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = seed;
+        self
+    }
+
+    pub fn invariant_checker(mut self, invariant_checker: I) -> Self {
+        self.invariant_checker = Some(invariant_checker);
+        self
+    }
+
+    /// See [`Simulator::add_invariant_checker`].
+    pub fn checker(
+        mut self,
+        name: impl Into<String>,
+        schedule: CheckSchedule,
+        checker: impl InvariantChecker<N, C> + 'static,
+    ) -> Self {
+        let name = name.into();
+        let checker_name = name.clone();
+        self.checkers.push(Checker {
+            name,
+            schedule,
+            add: Box::new(move |simulator| {
+                simulator.add_invariant_checker(checker_name, schedule, checker)
+            }),
+        });
+        self
+    }
+
+    /// See [`Simulator::add_observer`].
+    pub fn observer(mut self, observer: Rc<RefCell<dyn SimulationObserver<N::Message>>>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// See [`Simulator::add_rule`].
+    pub fn rule(mut self, rule: Rule<N::Message>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// See [`Simulator::add_fault_script`].
+    pub fn fault_script(mut self, script: FaultScript<N::Message>) -> Self {
+        self.fault_scripts.push(script);
+        self
+    }
+
+    /// See [`Simulator::set_node_factory`].
+    pub fn node_factory(mut self, factory: impl Fn(NodeId) -> N + 'static) -> Self {
+        self.node_factory = Some(Box::new(move |simulator| {
+            simulator.set_node_factory(factory)
+        }));
+        self
+    }
+
+    /// See [`Simulator::record_trace`].
+    pub fn record_trace(mut self) -> Self {
+        self.record_trace = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Simulator<N, C, I>, BuildError> {
+        let invariant_checker = self
+            .invariant_checker
+            .ok_or(BuildError::MissingInvariantChecker)?;
+        if let Some(checker) = self
+            .checkers
+            .iter()
+            .find(|checker| !checker.schedule.is_valid())
+        {
+            return Err(BuildError::InvalidCheckSchedule(checker.name.clone()));
+        }
+        let mut simulator = Simulator::try_new(
+            self.start_time.unwrap_or_else(Instant::now),
+            self.nodes,
+            self.clients,
+            self.config,
+            invariant_checker,
+        )?;
+
+        if self.record_trace {
+            simulator.record_trace();
+        }
+        for observer in self.observers {
+            simulator.add_observer(observer);
+        }
+        for rule in self.rules {
+            simulator.add_rule(rule);
+        }
+        for script in self.fault_scripts {
+            simulator.add_fault_script(script);
+        }
+        for checker in self.checkers {
+            (checker.add)(&mut simulator);
+        }
+        if let Some(set_node_factory) = self.node_factory {
+            set_node_factory(&mut simulator);
+        }
+        Ok(simulator)
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Instant;
+
+use crate::node::NodeId;
+
+/// Identifies an operation within a [`History`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct OperationId(usize);
+
+/// An operation invoked by a client, e.g., a read of a key.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Operation<Op, Ret> {
+    pub client: NodeId,
+    pub op: Op,
+    /// Simulated time at which the client invoked the operation.
+    pub invoked_at: Instant,
+    pub outcome: Outcome<Ret>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Outcome<Ret> {
+    /// The operation completed with the given result.
+    Ok { ret: Ret, completed_at: Instant },
+    /// The operation definitely did not take effect.
+    Fail { completed_at: Instant },
+    /// The client never learned the result (e.g., it timed out or crashed),
+    /// so the operation may or may not have taken effect.
+    Pending,
+}
+
+/// Records the operations of clients with simulated timestamps, e.g., for a
+/// linearizability check at the end of the simulation. Clients either share a
+/// history or record their own histories that are merged for the check.
+#[derive(Debug, Clone)]
+pub struct History<Op, Ret> {
+    operations: Vec<Operation<Op, Ret>>,
+}
+
+impl<Op, Ret> Default for History<Op, Ret> {
+    fn default() -> Self {
+        History { operations: vec![] }
+    }
+}
+
+impl<Op: Clone, Ret: Clone> History<Op, Ret> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges the histories of several clients.
+    pub fn merged<'a>(histories: impl IntoIterator<Item = &'a History<Op, Ret>>) -> Self
+    where
+        Op: 'a,
+        Ret: 'a,
+    {
+        History {
+            operations: histories
+                .into_iter()
+                .flat_map(|history| history.operations.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// Records the invocation of an operation. The returned id is used to
+    /// record its outcome.
+    pub fn invoke(&mut self, client: NodeId, op: Op, now: Instant) -> OperationId {
+        self.operations.push(Operation {
+            client,
+            op,
+            invoked_at: now,
+            outcome: Outcome::Pending,
+        });
+        OperationId(self.operations.len() - 1)
+    }
+
+    /// Records that the operation completed with the given result.
+    pub fn complete(&mut self, id: OperationId, ret: Ret, now: Instant) {
+        self.operations[id.0].outcome = Outcome::Ok {
+            ret,
+            completed_at: now,
+        };
+    }
+
+    /// Records that the operation definitely did not take effect.
+    pub fn fail(&mut self, id: OperationId, now: Instant) {
+        self.operations[id.0].outcome = Outcome::Fail { completed_at: now };
+    }
+
+    pub fn operations(&self) -> &[Operation<Op, Ret>] {
+        &self.operations
+    }
+}
+
+impl<Op, Ret> FromIterator<Operation<Op, Ret>> for History<Op, Ret> {
+    fn from_iter<T: IntoIterator<Item = Operation<Op, Ret>>>(iter: T) -> Self {
+        History {
+            operations: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::{self, Debug},
+    hash::Hash,
+    time::Instant,
+};
+
+use super::{History, Operation, Outcome};
+
+/// Sequential specification of a data type, used to check whether a concurrent
+/// history is linearizable.
+pub trait SequentialModel: Clone + Eq + Hash {
+    type Op: Clone + Debug;
+    type Ret: Clone + Debug + PartialEq;
+
+    /// Applies the operation and returns the new state and the result.
+    fn step(&self, op: &Self::Op) -> (Self, Self::Ret);
+}
+
+/// A single register that supports reads, writes and compare-and-set.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Register<V> {
+    pub value: Option<V>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RegisterOp<V> {
+    Read,
+    Write(V),
+    Cas { expected: V, new: V },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RegisterRet<V> {
+    Value(Option<V>),
+    Written,
+    Cas(bool),
+}
+
+impl<V: Clone + Debug + Eq + Hash> SequentialModel for Register<V> {
+    type Op = RegisterOp<V>;
+    type Ret = RegisterRet<V>;
+
+    fn step(&self, op: &Self::Op) -> (Self, Self::Ret) {
+        match op {
+            RegisterOp::Read => (self.clone(), RegisterRet::Value(self.value.clone())),
+            RegisterOp::Write(value) => (
+                Register {
+                    value: Some(value.clone()),
+                },
+                RegisterRet::Written,
+            ),
+            RegisterOp::Cas { expected, new } => {
+                if self.value.as_ref() == Some(expected) {
+                    (
+                        Register {
+                            value: Some(new.clone()),
+                        },
+                        RegisterRet::Cas(true),
+                    )
+                } else {
+                    (self.clone(), RegisterRet::Cas(false))
+                }
+            }
+        }
+    }
+}
+
+/// A key-value map.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct KvMap<K, V> {
+    pub entries: BTreeMap<K, V>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum KvOp<K, V> {
+    Get(K),
+    Put(K, V),
+    Delete(K),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum KvRet<V> {
+    Value(Option<V>),
+    Ok,
+}
+
+impl<K, V> SequentialModel for KvMap<K, V>
+where
+    K: Clone + Debug + Ord + Hash,
+    V: Clone + Debug + Eq + Hash,
+{
+    type Op = KvOp<K, V>;
+    type Ret = KvRet<V>;
+
+    fn step(&self, op: &Self::Op) -> (Self, Self::Ret) {
+        match op {
+            KvOp::Get(key) => (self.clone(), KvRet::Value(self.entries.get(key).cloned())),
+            KvOp::Put(key, value) => {
+                let mut next = self.clone();
+                next.entries.insert(key.clone(), value.clone());
+                (next, KvRet::Ok)
+            }
+            KvOp::Delete(key) => {
+                let mut next = self.clone();
+                next.entries.remove(key);
+                (next, KvRet::Ok)
+            }
+        }
+    }
+}
+
+/// A FIFO queue.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Queue<V> {
+    pub items: VecDeque<V>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum QueueOp<V> {
+    Enqueue(V),
+    Dequeue,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum QueueRet<V> {
+    Enqueued,
+    Value(Option<V>),
+}
+
+impl<V: Clone + Debug + Eq + Hash> SequentialModel for Queue<V> {
+    type Op = QueueOp<V>;
+    type Ret = QueueRet<V>;
+
+    fn step(&self, op: &Self::Op) -> (Self, Self::Ret) {
+        let mut next = self.clone();
+        match op {
+            QueueOp::Enqueue(value) => {
+                next.items.push_back(value.clone());
+                (next, QueueRet::Enqueued)
+            }
+            QueueOp::Dequeue => {
+                let value = next.items.pop_front();
+                (next, QueueRet::Value(value))
+            }
+        }
+    }
+}
+
+/// A history that is not linearizable. The operations form a minimal
+/// sub-history that still shows the violation: the culprit is the operation
+/// that cannot be linearized together with the operations invoked before it.
+/// If the culprit could be linearized when ignoring its timing (e.g., a stale
+/// read), only operations that keep it that way are removed, so the
+/// operations that explain the violation (e.g., the writes) are kept.
+#[derive(Debug, Clone)]
+pub struct LinearizabilityViolation<Op, Ret> {
+    pub operations: Vec<Operation<Op, Ret>>,
+    /// Index of the culprit in the operations.
+    pub culprit: usize,
+}
+
+impl<Op: Debug, Ret: Debug> fmt::Display for LinearizabilityViolation<Op, Ret> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "History is not linearizable. Minimal violating sub-history:"
+        )?;
+        let start = self.operations.iter().map(|op| op.invoked_at).min();
+        let since_start = |time: Instant| time.duration_since(start.unwrap());
+        for (i, operation) in self.operations.iter().enumerate() {
+            let outcome = match &operation.outcome {
+                Outcome::Ok { ret, completed_at } => {
+                    format!("{:?} at {:?}", ret, since_start(*completed_at))
+                }
+                Outcome::Fail { completed_at } => {
+                    format!("failed at {:?}", since_start(*completed_at))
+                }
+                Outcome::Pending => "pending".to_string(),
+            };
+            let marker = if i == self.culprit { " (culprit)" } else { "" };
+            writeln!(
+                f,
+                "  {}: {:?} invoked at {:?} -> {}{}",
+                operation.client,
+                operation.op,
+                since_start(operation.invoked_at),
+                outcome,
+                marker
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether the history is linearizable with respect to the sequential
+/// model, starting from the given initial state. Failed operations are
+/// ignored, pending operations may or may not have taken effect. On failure, a
+/// minimal non-linearizable sub-history is returned.
+pub fn check_linearizability<M: SequentialModel>(
+    history: &History<M::Op, M::Ret>,
+    initial: M,
+) -> Result<(), LinearizabilityViolation<M::Op, M::Ret>> {
+    let mut operations = history
+        .operations()
+        .iter()
+        .filter(|operation| !matches!(operation.outcome, Outcome::Fail { .. }))
+        .cloned()
+        .collect::<Vec<_>>();
+    operations.sort_by_key(|operation| operation.invoked_at);
+
+    if is_linearizable(&operations, &initial) {
+        return Ok(());
+    }
+
+    Err(shrink(operations, &initial))
+}
+
+// Shrinks a non-linearizable history to a minimal sub-history, see
+// `LinearizabilityViolation`.
+fn shrink<M: SequentialModel>(
+    mut operations: Vec<Operation<M::Op, M::Ret>>,
+    initial: &M,
+) -> LinearizabilityViolation<M::Op, M::Ret> {
+    let mut culprit = (1..=operations.len())
+        .find(|len| !is_linearizable(&operations[..*len], initial))
+        .expect("History must not be linearizable")
+        - 1;
+    let timing_violation = is_linearizable(&relax(&operations, culprit), initial);
+
+    loop {
+        let mut removed_any = false;
+        let mut i = 0;
+        while i < operations.len() {
+            if i == culprit {
+                i += 1;
+                continue;
+            }
+
+            let mut candidate = operations.clone();
+            candidate.remove(i);
+            let candidate_culprit = if i < culprit { culprit - 1 } else { culprit };
+            let still_violated = !is_linearizable(&candidate, initial)
+                && (!timing_violation
+                    || is_linearizable(&relax(&candidate, candidate_culprit), initial));
+            if still_violated {
+                operations = candidate;
+                culprit = candidate_culprit;
+                removed_any = true;
+            } else {
+                i += 1;
+            }
+        }
+
+        if !removed_any {
+            return LinearizabilityViolation {
+                operations,
+                culprit,
+            };
+        }
+    }
+}
+
+// Lets the operation overlap with all other operations, i.e., ignores its
+// real-time order.
+fn relax<Op: Clone, Ret: Clone>(
+    operations: &[Operation<Op, Ret>],
+    index: usize,
+) -> Vec<Operation<Op, Ret>> {
+    let mut relaxed = operations.to_vec();
+    let first_invocation = operations.iter().map(|op| op.invoked_at).min();
+    let last_completion = operations
+        .iter()
+        .filter_map(|op| match &op.outcome {
+            Outcome::Ok { completed_at, .. } => Some(*completed_at),
+            _ => None,
+        })
+        .max();
+
+    let operation = &mut relaxed[index];
+    operation.invoked_at = first_invocation.unwrap();
+    if let (Outcome::Ok { completed_at, .. }, Some(last_completion)) =
+        (&mut operation.outcome, last_completion)
+    {
+        *completed_at = last_completion;
+    }
+    relaxed
+}
+
+// Searches for a linearization (Wing & Gong with memoization as in Lowe's
+// algorithm): operations are linearized one by one, choosing among those that
+// were invoked before any remaining operation completed. Visited combinations
+// of linearized operations and model states are skipped.
+fn is_linearizable<M: SequentialModel>(
+    operations: &[Operation<M::Op, M::Ret>],
+    initial: &M,
+) -> bool {
+    let completed_at = |operation: &Operation<M::Op, M::Ret>| match &operation.outcome {
+        Outcome::Ok { completed_at, .. } => Some(*completed_at),
+        _ => None,
+    };
+
+    let mut seen = HashSet::new();
+    let mut stack = vec![(vec![false; operations.len()], initial.clone())];
+    while let Some((linearized, state)) = stack.pop() {
+        let remaining = operations
+            .iter()
+            .zip(&linearized)
+            .filter(|(_, linearized)| !**linearized)
+            .map(|(operation, _)| operation);
+
+        // pending operations do not need to be linearized
+        let Some(earliest_completion) = remaining.filter_map(completed_at).min() else {
+            return true;
+        };
+
+        for (i, operation) in operations.iter().enumerate() {
+            if linearized[i] || operation.invoked_at > earliest_completion {
+                continue;
+            }
+
+            let (next_state, ret) = state.step(&operation.op);
+            if let Outcome::Ok { ret: expected, .. } = &operation.outcome {
+                if ret != *expected {
+                    continue;
+                }
+            }
+
+            let mut next_linearized = linearized.clone();
+            next_linearized[i] = true;
+            if seen.insert((next_linearized.clone(), next_state.clone())) {
+                stack.push((next_linearized, next_state));
+            }
+        }
+    }
+    false
+}
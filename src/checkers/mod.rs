@@ -0,0 +1,7 @@
+mod history;
+mod linearizability;
+mod transactions;
+
+pub use history::*;
+pub use linearizability::*;
+pub use transactions::*;
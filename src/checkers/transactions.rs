@@ -0,0 +1,364 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Debug},
+    hash::Hash,
+};
+
+use super::{History, Operation, Outcome};
+
+/// A micro-operation of a transaction on a list-append or read-write register
+/// key. Appended and written values must be unique per key, so the writer of
+/// every observed value is known.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum MicroOp<K, V> {
+    /// Appends the value to the list at the key.
+    Append(K, V),
+    /// Reads the list at the key. When invoking the transaction, the list is
+    /// unknown and should be left empty.
+    ReadList(K, Vec<V>),
+    /// Writes the value to the register at the key.
+    Write(K, V),
+    /// Reads the register at the key (None if it was never written).
+    Read(K, Option<V>),
+}
+
+/// The micro-operations of a transaction. The history records the requested
+/// transaction as the operation and the transaction including the values that
+/// were read as the result.
+pub type Transaction<K, V> = Vec<MicroOp<K, V>>;
+
+pub type TransactionHistory<K, V> = History<Transaction<K, V>, Transaction<K, V>>;
+
+pub type TransactionOperation<K, V> = Operation<Transaction<K, V>, Transaction<K, V>>;
+
+/// The isolation level a system claims to provide. Each level proscribes a set
+/// of anomalies.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IsolationLevel {
+    /// Proscribes G0 and G1c.
+    ReadCommitted,
+    /// Proscribes G0, G1c and G-single.
+    SnapshotIsolation,
+    /// Proscribes all anomalies.
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn proscribes(&self, kind: AnomalyKind) -> bool {
+        match self {
+            IsolationLevel::ReadCommitted => matches!(kind, AnomalyKind::G0 | AnomalyKind::G1c),
+            IsolationLevel::SnapshotIsolation => kind != AnomalyKind::G2,
+            IsolationLevel::Serializable => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AnomalyKind {
+    /// Write cycle: a cycle of write-write dependencies.
+    G0,
+    /// Cyclic information flow: a cycle of write-write and write-read
+    /// dependencies.
+    G1c,
+    /// Read skew: a cycle with exactly one read-write anti-dependency.
+    GSingle,
+    /// A cycle with multiple read-write anti-dependencies, e.g., write skew.
+    G2,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Dependency {
+    /// The next transaction overwrote a value written by this transaction.
+    WriteWrite,
+    /// The next transaction read a value written by this transaction.
+    WriteRead,
+    /// The next transaction overwrote a value read by this transaction.
+    ReadWrite,
+}
+
+/// A transaction in a dependency cycle and its dependency on the next
+/// transaction of the cycle (the last one depends on the first one).
+#[derive(Debug, Clone)]
+pub struct CycleStep<K, V> {
+    pub transaction: TransactionOperation<K, V>,
+    pub dependency: Dependency,
+    pub key: K,
+}
+
+#[derive(Debug, Clone)]
+pub struct Anomaly<K, V> {
+    pub kind: AnomalyKind,
+    pub cycle: Vec<CycleStep<K, V>>,
+}
+
+impl<K: Debug, V: Debug> fmt::Display for Anomaly<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?} anomaly:", self.kind)?;
+        for step in &self.cycle {
+            let ops = match &step.transaction.outcome {
+                Outcome::Ok { ret, .. } => ret,
+                _ => &step.transaction.op,
+            };
+            writeln!(
+                f,
+                "  {}: {:?} -{:?} on {:?}->",
+                step.transaction.client, ops, step.dependency, step.key
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Cycle of transactions (by index), each with the edge to the next one.
+type Cycle<K> = Vec<(usize, Edge<K>)>;
+
+// Dependency edge between transactions (by index).
+#[derive(Debug, Clone)]
+struct Edge<K> {
+    to: usize,
+    dependency: Dependency,
+    key: K,
+}
+
+/// Checks a transaction history for anomalies proscribed by the isolation
+/// level (in the style of Elle). Committed transactions (with an `Ok`
+/// outcome) and the writes of pending transactions are used to infer a
+/// dependency graph, whose cycles are reported as anomalies. For list-append
+/// keys the version order follows from the longest read of each list. For
+/// register keys, the version order is only known where a transaction reads a
+/// key and then writes it.
+pub fn check_transactions<K, V>(
+    history: &TransactionHistory<K, V>,
+    level: IsolationLevel,
+) -> Result<(), Vec<Anomaly<K, V>>>
+where
+    K: Clone + Debug + Eq + Hash,
+    V: Clone + Debug + Eq + Hash,
+{
+    let transactions = history
+        .operations()
+        .iter()
+        .filter(|operation| !matches!(operation.outcome, Outcome::Fail { .. }))
+        .cloned()
+        .collect::<Vec<_>>();
+    let graph = DependencyGraph::new(&transactions);
+
+    let anomalies = graph
+        .find_anomalies()
+        .into_iter()
+        .filter(|(kind, _)| level.proscribes(*kind))
+        .map(|(kind, cycle)| Anomaly {
+            kind,
+            cycle: cycle
+                .into_iter()
+                .map(|(from, edge)| CycleStep {
+                    transaction: transactions[from].clone(),
+                    dependency: edge.dependency,
+                    key: edge.key,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    if anomalies.is_empty() {
+        Ok(())
+    } else {
+        Err(anomalies)
+    }
+}
+
+struct DependencyGraph<K> {
+    edges: Vec<Vec<Edge<K>>>,
+}
+
+impl<K: Clone + Eq + Hash> DependencyGraph<K> {
+    fn new<V: Clone + Eq + Hash>(transactions: &[TransactionOperation<K, V>]) -> Self {
+        // Committed transactions are known with their reads, pending ones
+        // only with their writes.
+        let committed = |i: usize| match &transactions[i].outcome {
+            Outcome::Ok { ret, .. } => Some(ret),
+            _ => None,
+        };
+        let mops = |i: usize| committed(i).unwrap_or(&transactions[i].op);
+
+        let mut writers = HashMap::new();
+        for i in 0..transactions.len() {
+            for mop in mops(i) {
+                if let MicroOp::Append(key, value) | MicroOp::Write(key, value) = mop {
+                    writers.insert((key.clone(), value.clone()), i);
+                }
+            }
+        }
+
+        // version orders per key (None is the initial version of registers)
+        let mut list_orders: HashMap<K, Vec<V>> = HashMap::new();
+        let mut register_successors: HashMap<(K, Option<V>), HashSet<V>> = HashMap::new();
+        for i in 0..transactions.len() {
+            let Some(mops) = committed(i) else {
+                continue;
+            };
+            let mut last_read: HashMap<&K, Option<&V>> = HashMap::new();
+            for mop in mops {
+                match mop {
+                    MicroOp::ReadList(key, values) => {
+                        let order = list_orders.entry(key.clone()).or_default();
+                        if values.len() > order.len() {
+                            *order = values.clone();
+                        }
+                    }
+                    MicroOp::Read(key, value) => {
+                        last_read.insert(key, value.as_ref());
+                    }
+                    MicroOp::Write(key, value) => {
+                        if let Some(read) = last_read.insert(key, Some(value)) {
+                            register_successors
+                                .entry((key.clone(), read.cloned()))
+                                .or_default()
+                                .insert(value.clone());
+                        }
+                    }
+                    MicroOp::Append(..) => {}
+                }
+            }
+        }
+
+        let mut graph = DependencyGraph {
+            edges: vec![vec![]; transactions.len()],
+        };
+        let writer = |key: &K, value: &V| writers.get(&(key.clone(), value.clone())).copied();
+
+        // write-write dependencies
+        for (key, order) in &list_orders {
+            for pair in order.windows(2) {
+                if let (Some(from), Some(to)) = (writer(key, &pair[0]), writer(key, &pair[1])) {
+                    graph.add(from, to, Dependency::WriteWrite, key);
+                }
+            }
+        }
+        for ((key, previous), successors) in &register_successors {
+            let Some(previous) = previous else {
+                continue;
+            };
+            for successor in successors {
+                if let (Some(from), Some(to)) = (writer(key, previous), writer(key, successor)) {
+                    graph.add(from, to, Dependency::WriteWrite, key);
+                }
+            }
+        }
+
+        // write-read and read-write dependencies
+        for i in 0..transactions.len() {
+            let Some(mops) = committed(i) else {
+                continue;
+            };
+            for mop in mops {
+                match mop {
+                    MicroOp::ReadList(key, values) => {
+                        if let Some(from) = values.last().and_then(|value| writer(key, value)) {
+                            graph.add(from, i, Dependency::WriteRead, key);
+                        }
+                        let next = list_orders[key].get(values.len());
+                        if let Some(to) = next.and_then(|value| writer(key, value)) {
+                            graph.add(i, to, Dependency::ReadWrite, key);
+                        }
+                    }
+                    MicroOp::Read(key, value) => {
+                        if let Some(from) = value.as_ref().and_then(|value| writer(key, value)) {
+                            graph.add(from, i, Dependency::WriteRead, key);
+                        }
+                        let successors = register_successors.get(&(key.clone(), value.clone()));
+                        for successor in successors.into_iter().flatten() {
+                            if let Some(to) = writer(key, successor) {
+                                graph.add(i, to, Dependency::ReadWrite, key);
+                            }
+                        }
+                    }
+                    MicroOp::Append(..) | MicroOp::Write(..) => {}
+                }
+            }
+        }
+
+        graph
+    }
+
+    fn add(&mut self, from: usize, to: usize, dependency: Dependency, key: &K) {
+        if from != to {
+            self.edges[from].push(Edge {
+                to,
+                dependency,
+                key: key.clone(),
+            });
+        }
+    }
+
+    // Finds cycles, classified by the weakest anomaly they constitute. Every
+    // set of transactions is only reported once.
+    fn find_anomalies(&self) -> Vec<(AnomalyKind, Cycle<K>)> {
+        use Dependency::*;
+
+        let mut anomalies = vec![];
+        let mut reported = HashSet::new();
+        let searches = [
+            (AnomalyKind::G0, WriteWrite, &[WriteWrite][..]),
+            (AnomalyKind::G1c, WriteRead, &[WriteWrite, WriteRead][..]),
+            (
+                AnomalyKind::GSingle,
+                ReadWrite,
+                &[WriteWrite, WriteRead][..],
+            ),
+            (
+                AnomalyKind::G2,
+                ReadWrite,
+                &[WriteWrite, WriteRead, ReadWrite][..],
+            ),
+        ];
+
+        for (kind, first, rest) in searches {
+            for from in 0..self.edges.len() {
+                for edge in self.edges[from].iter().filter(|e| e.dependency == first) {
+                    // close the cycle from the end of the edge back to its start
+                    let Some(path) = self.find_path(edge.to, from, rest) else {
+                        continue;
+                    };
+                    let mut cycle = vec![(from, edge.clone())];
+                    cycle.extend(path);
+
+                    let mut members = cycle.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+                    members.sort_unstable();
+                    if reported.insert(members) {
+                        anomalies.push((kind, cycle));
+                    }
+                }
+            }
+        }
+        anomalies
+    }
+
+    // Shortest path using only the given dependencies (breadth-first search).
+    fn find_path(&self, from: usize, to: usize, dependencies: &[Dependency]) -> Option<Cycle<K>> {
+        let mut predecessors: HashMap<usize, (usize, &Edge<K>)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut visited = HashSet::from([from]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![];
+                let mut node = to;
+                while node != from {
+                    let (previous, edge) = predecessors[&node];
+                    path.push((previous, edge.clone()));
+                    node = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for edge in &self.edges[current] {
+                if dependencies.contains(&edge.dependency) && visited.insert(edge.to) {
+                    predecessors.insert(edge.to, (current, edge));
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,124 @@
+use rand::prelude::*;
+use std::time::Instant;
+use tracing::info;
+
+use crate::{
+    node::{NodeId, NodeState},
+    DeterministicClient, FailureConfiguration, RequestMark,
+};
+
+/// Wraps a client with failure behavior, analogous to [`crate::Node`]. Clients
+/// only fail if a client failure configuration is given.
+#[derive(Debug, Clone)]
+pub struct Client<C: DeterministicClient> {
+    client: C,
+    state: NodeState,
+    failure_config: Option<FailureConfiguration>,
+    start_time: Instant,
+}
+
+impl<C: DeterministicClient> Client<C> {
+    pub fn new(
+        client: C,
+        failure_config: Option<FailureConfiguration>,
+        rng: &mut dyn RngCore,
+        start_time: Instant,
+    ) -> Self {
+        let state = match &failure_config {
+            Some(failure_config) => NodeState::new(start_time, failure_config, rng),
+            None => NodeState::Normal { failure_time: None },
+        };
+
+        Client {
+            client,
+            state,
+            failure_config,
+            start_time,
+        }
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.client.id()
+    }
+
+    pub fn is_up(&self) -> bool {
+        !self.state.is_failed()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.client.finished()
+    }
+
+    /// Restarts the client if it crashed. The client will not fail again.
+    pub(crate) fn heal(&mut self, now: Instant, rand: &mut dyn RngCore) {
+        self.failure_config = None;
+        if self.state.is_failed() {
+            info!(
+                time = ?now.duration_since(self.start_time),
+                client = ?self.id(),
+                "Client restarted"
+            );
+            let nonce = rand.next_u64();
+            self.client.recover(now, nonce);
+        }
+        self.state = NodeState::Normal { failure_time: None };
+    }
+
+    /// Returns the marks of the last message or tick the client handled. They
+    /// are taken even if the client is down now, so they are never recorded
+    /// late.
+    pub(crate) fn take_request_marks(&mut self) -> Vec<RequestMark> {
+        self.client.request_marks()
+    }
+
+    fn has_failed(&mut self, now: Instant, rand: &mut dyn RngCore) -> bool {
+        let Some(failure_config) = &self.failure_config else {
+            return false;
+        };
+
+        if let Some(new_state) = self.state.transition(now, true, failure_config, rand) {
+            if new_state.is_normal() {
+                info!(
+                    time = ?now.duration_since(self.start_time),
+                    client = ?self.id(),
+                    "Client restarted"
+                );
+                let nonce = rand.next_u64();
+                self.client.recover(now, nonce);
+            } else {
+                info!(
+                    time = ?now.duration_since(self.start_time),
+                    client = ?self.id(),
+                    "Client crashed"
+                );
+            }
+
+            self.state = new_state;
+        }
+
+        self.state.is_failed()
+    }
+
+    pub fn tick(&mut self, now: Instant, rand: &mut dyn RngCore) -> Vec<C::Message> {
+        if self.has_failed(now, rand) {
+            return vec![];
+        }
+        self.client.tick(now)
+    }
+
+    pub fn process_message(
+        &mut self,
+        msg: C::Message,
+        now: Instant,
+        rand: &mut dyn RngCore,
+    ) -> Vec<C::Message> {
+        if self.has_failed(now, rand) {
+            return vec![];
+        }
+        self.client.process_message(msg, now)
+    }
+}
@@ -1,15 +1,30 @@
-use std::time::Duration;
+use std::{error::Error, fmt, time::Duration};
 
-use crate::NetworkConfig;
+use crate::{LatencyDistribution, NetworkConfig, SchedulingStrategy};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct Configuration {
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub tick_interval: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub max_sim_time: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::seed"))]
     pub seed: u64,
     pub check_invariants_frequency: usize,
     pub network_config: NetworkConfig,
     pub failure_config: FailureConfiguration,
+    // Clients only fail if a failure configuration is given.
+    pub client_failure_config: Option<FailureConfiguration>,
+    pub processing_config: ProcessingConfiguration,
+    pub scheduling: SchedulingStrategy,
+    // If given, faults are healed once the clients finished and the simulation
+    // continues until it settles.
+    pub settle_config: Option<SettleConfiguration>,
 }
 
 impl Default for Configuration {
@@ -21,13 +36,27 @@ impl Default for Configuration {
             check_invariants_frequency: 1,
             network_config: NetworkConfig::default(),
             failure_config: FailureConfiguration::default(),
+            client_failure_config: None,
+            processing_config: ProcessingConfiguration::default(),
+            scheduling: SchedulingStrategy::default(),
+            settle_config: None,
         }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct FailureConfiguration {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::config_file::optional_duration")
+    )]
     pub mean_time_between_failures: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub mean_time_to_recover: Duration,
 }
 
@@ -39,3 +68,226 @@ impl Default for FailureConfiguration {
         }
     }
 }
+
+/// Simulated time nodes need to handle a message. A node handles one event
+/// at a time, so events arriving while it is busy are queued. The time is
+/// sampled uniformly between the bounds unless the node reports its own
+/// processing time. Ticks take no time unless the node reports one (see
+/// [`crate::DeterministicNode::tick_processing_time`]).
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ProcessingConfiguration {
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
+    pub min_processing_time: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
+    pub max_processing_time: Duration,
+}
+
+/// Final phase of a simulation: once the clients finished, all faults are
+/// healed (see [`crate::Simulator::heal`]) and the simulation continues until
+/// no messages were in flight for the quiet period. Then the invariants are
+/// checked a last time, e.g., that all replicas converged.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct SettleConfiguration {
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
+    pub quiet_period: Duration,
+    /// The simulation times out if it does not settle within this time after
+    /// healing.
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
+    pub max_settle_time: Duration,
+}
+
+impl Default for SettleConfiguration {
+    fn default() -> Self {
+        SettleConfiguration {
+            quiet_period: Duration::from_secs(1),
+            max_settle_time: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A configuration field with an invalid value.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidField {
+    /// Path of the field, e.g., `network_config.duplicate_probability`.
+    pub field: String,
+    pub reason: String,
+}
+
+/// All invalid fields of a configuration.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigurationError {
+    pub invalid_fields: Vec<InvalidField>,
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for InvalidField { field, reason } in &self.invalid_fields {
+            write!(f, "\n  {}: {}", field, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigurationError {}
+
+impl Configuration {
+    /// Checks that all values are in range, e.g., that probabilities are
+    /// between 0 and 1 and that the minimum latency does not exceed the
+    /// maximum. Returns all invalid fields at once.
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        let mut validator = Validator::default();
+        validator.check(
+            !self.tick_interval.is_zero(),
+            "tick_interval",
+            "must be positive",
+        );
+        validator.check(
+            self.check_invariants_frequency > 0,
+            "check_invariants_frequency",
+            "must be at least 1",
+        );
+        validator.network(&self.network_config);
+        validator.failures(&self.failure_config, "failure_config");
+        if let Some(client_failure_config) = &self.client_failure_config {
+            validator.failures(client_failure_config, "client_failure_config");
+        }
+        validator.check(
+            self.processing_config.min_processing_time
+                <= self.processing_config.max_processing_time,
+            "processing_config.min_processing_time",
+            "must not exceed max_processing_time",
+        );
+        if let SchedulingStrategy::Pct(pct) = &self.scheduling {
+            validator.check(pct.depth > 0, "scheduling.depth", "must be at least 1");
+        }
+        if let Some(settle_config) = &self.settle_config {
+            validator.check(
+                settle_config.quiet_period <= settle_config.max_settle_time,
+                "settle_config.quiet_period",
+                "must not exceed max_settle_time",
+            );
+        }
+
+        if validator.invalid_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigurationError {
+                invalid_fields: validator.invalid_fields,
+            })
+        }
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    invalid_fields: Vec<InvalidField>,
+}
+
+impl Validator {
+    fn check(&mut self, valid: bool, field: &str, reason: &str) {
+        if !valid {
+            self.invalid_fields.push(InvalidField {
+                field: field.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    fn probability(&mut self, probability: f64, field: &str) {
+        self.check(
+            (0.0..=1.0).contains(&probability),
+            field,
+            "must be between 0 and 1",
+        );
+    }
+
+    // Mean times are the parameter of an exponential distribution, so they
+    // must not be zero.
+    fn mean_time(&mut self, mean_time: Option<Duration>, field: &str) {
+        if let Some(mean_time) = mean_time {
+            self.check(!mean_time.is_zero(), field, "must be positive");
+        }
+    }
+
+    fn network(&mut self, config: &NetworkConfig) {
+        self.check(
+            config.min_message_latency <= config.max_message_latency,
+            "network_config.min_message_latency",
+            "must not exceed max_message_latency",
+        );
+        match config.latency_distribution {
+            LatencyDistribution::Exponential { rate } => self.check(
+                rate.is_finite() && rate > 0.0,
+                "network_config.latency_distribution.rate",
+                "must be positive",
+            ),
+            LatencyDistribution::Uniform => {}
+            LatencyDistribution::Normal { mean, std_dev } => {
+                self.check(
+                    mean.is_finite(),
+                    "network_config.latency_distribution.mean",
+                    "must be finite",
+                );
+                self.check(
+                    std_dev.is_finite() && std_dev >= 0.0,
+                    "network_config.latency_distribution.std_dev",
+                    "must not be negative",
+                );
+            }
+        }
+        self.probability(
+            config.duplicate_probability,
+            "network_config.duplicate_probability",
+        );
+        self.probability(config.hold_probability, "network_config.hold_probability");
+        self.mean_time(
+            config.mean_time_between_link_failures,
+            "network_config.mean_time_between_link_failures",
+        );
+        if config.mean_time_between_link_failures.is_some() {
+            self.mean_time(
+                Some(config.mean_link_recovery_time),
+                "network_config.mean_link_recovery_time",
+            );
+        }
+        self.mean_time(
+            config.mean_time_between_partitions,
+            "network_config.mean_time_between_partitions",
+        );
+        if config.mean_time_between_partitions.is_some() {
+            self.mean_time(
+                Some(config.mean_partition_recovery_time),
+                "network_config.mean_partition_recovery_time",
+            );
+        }
+        self.check(
+            config.link_queue_capacity != Some(0),
+            "network_config.link_queue_capacity",
+            "must be at least 1",
+        );
+    }
+
+    fn failures(&mut self, config: &FailureConfiguration, prefix: &str) {
+        self.mean_time(
+            config.mean_time_between_failures,
+            &format!("{}.mean_time_between_failures", prefix),
+        );
+        if config.mean_time_between_failures.is_some() {
+            self.mean_time(
+                Some(config.mean_time_to_recover),
+                &format!("{}.mean_time_to_recover", prefix),
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::Configuration;
+
+/// Error when loading a configuration from a file.
+#[derive(Debug)]
+pub enum ConfigFileError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `.toml` nor `.json`.
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFileError::Io(error) => write!(f, "Failed to read configuration: {}", error),
+            ConfigFileError::Toml(error) => write!(f, "Invalid TOML configuration: {}", error),
+            ConfigFileError::Json(error) => write!(f, "Invalid JSON configuration: {}", error),
+            ConfigFileError::UnknownFormat(path) => {
+                write!(f, "Unknown configuration format: {}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for ConfigFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigFileError::Io(error) => Some(error),
+            ConfigFileError::Toml(error) => Some(error),
+            ConfigFileError::Json(error) => Some(error),
+            ConfigFileError::UnknownFormat(_) => None,
+        }
+    }
+}
+
+/// Configurations can be stored as TOML or JSON. Durations are written as
+/// strings like `"50ms"` or `"2s"` (or `"never"` for failures that should not
+/// happen) and missing fields take their default values, so a file only needs
+/// to list what differs from the defaults.
+impl Configuration {
+    pub fn from_toml(config: &str) -> Result<Self, ConfigFileError> {
+        toml::from_str(config).map_err(ConfigFileError::Toml)
+    }
+
+    pub fn from_json(config: &str) -> Result<Self, ConfigFileError> {
+        serde_json::from_str(config).map_err(ConfigFileError::Json)
+    }
+
+    /// Loads a configuration from a `.toml` or `.json` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigFileError> {
+        let path = path.as_ref();
+        let config = fs::read_to_string(path).map_err(ConfigFileError::Io)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&config),
+            Some("json") => Self::from_json(&config),
+            _ => Err(ConfigFileError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Returns the complete configuration (including defaults) as TOML, e.g.,
+    /// to store the configuration of a failing run. Fails only for numbers
+    /// that TOML cannot represent (counts above `i64::MAX`).
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+}
+
+// Units of durations in configuration files with their length in nanoseconds,
+// from largest to smallest.
+const UNITS: [(&str, u128); 6] = [
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Parses a duration like `"50ms"`, `"1.5s"` or `"2m"`. Supported units are
+/// `h`, `m`, `s`, `ms`, `us` and `ns`.
+pub(crate) fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration '{}', expected e.g. '50ms'", duration);
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .ok_or_else(invalid)?;
+    let (value, unit) = duration.split_at(split);
+    let (_, unit_nanos) = UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .ok_or_else(invalid)?;
+
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.is_empty() || fraction.contains('.') {
+        return Err(invalid());
+    }
+    let integer: u128 = integer.parse().map_err(|_| invalid())?;
+    let mut nanos = integer * unit_nanos;
+    let mut digit_nanos = *unit_nanos;
+    for digit in fraction.chars() {
+        digit_nanos /= 10;
+        nanos += digit.to_digit(10).ok_or_else(invalid)? as u128 * digit_nanos;
+    }
+    let nanos = u64::try_from(nanos).map_err(|_| invalid())?;
+    Ok(Duration::from_nanos(nanos))
+}
+
+/// Formats a duration with the largest unit that represents it exactly.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    let (unit, unit_nanos) = UNITS
+        .iter()
+        .find(|(_, unit_nanos)| nanos > 0 && nanos.is_multiple_of(*unit_nanos))
+        .unwrap_or(&("ms", 1_000_000));
+    format!("{}{}", nanos / unit_nanos, unit)
+}
+
+pub(crate) mod duration {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_duration(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let duration = String::deserialize(deserializer)?;
+        super::parse_duration(&duration).map_err(D::Error::custom)
+    }
+}
+
+// Optional durations are used for failures that might never happen. They are
+// written as "never" instead of being left out, because the defaults enable
+// failures.
+pub(crate) mod optional_duration {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    const NEVER: &str = "never";
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_str(&super::format_duration(*duration)),
+            None => serializer.serialize_str(NEVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        match Option::<String>::deserialize(deserializer)?.as_deref() {
+            None | Some(NEVER) => Ok(None),
+            Some(duration) => super::parse_duration(duration)
+                .map(Some)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
+// TOML integers are signed 64-bit, but seeds are often drawn from the whole u64
+// range. Seeds that do not fit are written as strings; both forms are read.
+pub(crate) mod seed {
+    use serde::{
+        de::{Error, Visitor},
+        Deserializer, Serializer,
+    };
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        match i64::try_from(*seed) {
+            Ok(seed) => serializer.serialize_i64(seed),
+            Err(_) => serializer.serialize_str(&seed.to_string()),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_any(SeedVisitor)
+    }
+
+    struct SeedVisitor;
+
+    impl Visitor<'_> for SeedVisitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a non-negative integer or a string containing one")
+        }
+
+        fn visit_u64<E: Error>(self, seed: u64) -> Result<u64, E> {
+            Ok(seed)
+        }
+
+        fn visit_i64<E: Error>(self, seed: i64) -> Result<u64, E> {
+            u64::try_from(seed).map_err(|_| E::custom(format!("Invalid seed {}", seed)))
+        }
+
+        fn visit_str<E: Error>(self, seed: &str) -> Result<u64, E> {
+            seed.parse()
+                .map_err(|_| E::custom(format!("Invalid seed '{}'", seed)))
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use tracing::info;
+
+use std::{cell::RefCell, collections::BTreeSet, time::Duration};
+
+use crate::Configuration;
+
+use super::simulate_with_config;
+
+thread_local! {
+    static COVERAGE: RefCell<BTreeSet<String>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+/// Reports that an abstract coverage point was reached, e.g., "entered view
+/// change" or "received stale prepare". Nodes and clients can call this from
+/// anywhere; the points are collected per run by the [`CoverageExplorer`].
+pub fn cover(point: &str) {
+    COVERAGE.with(|coverage| {
+        let mut coverage = coverage.borrow_mut();
+        if !coverage.contains(point) {
+            coverage.insert(point.to_string());
+        }
+    });
+}
+
+fn take_coverage() -> BTreeSet<String> {
+    COVERAGE.with(|coverage| std::mem::take(&mut *coverage.borrow_mut()))
+}
+
+/// Result of a coverage-guided exploration.
+#[derive(Debug, Clone)]
+pub struct ExplorationReport {
+    /// Number of simulations that were run.
+    pub runs: usize,
+    /// All coverage points reached in any run.
+    pub coverage: BTreeSet<String>,
+    /// Configurations that reached new coverage.
+    pub corpus: Vec<Configuration>,
+    /// Configurations for which the simulation did not finish.
+    pub failures: Vec<Configuration>,
+}
+
+/// Explores fault schedules guided by coverage, similar to a fuzzer. Every run
+/// mutates a configuration from the corpus (the seed and the fault
+/// parameters) and configurations that reach new coverage points are added to
+/// the corpus, so interesting fault schedules are explored further.
+pub struct CoverageExplorer {
+    base: Configuration,
+    corpus: Vec<Configuration>,
+    coverage: BTreeSet<String>,
+    rng: ChaCha8Rng,
+}
+
+impl CoverageExplorer {
+    /// Creates an explorer starting from the given configuration. The seed
+    /// determines the mutations, so explorations are reproducible.
+    pub fn new(base: Configuration, seed: u64) -> Self {
+        CoverageExplorer {
+            corpus: vec![base.clone()],
+            base,
+            coverage: BTreeSet::new(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Runs the given number of simulations. The closure runs a simulation for
+    /// a configuration and returns whether it finished (see
+    /// [`crate::Simulator::run`]). Invariant violations panic after logging the
+    /// configuration that caused them.
+    pub fn run(
+        &mut self,
+        iterations: usize,
+        mut simulate: impl FnMut(Configuration) -> bool,
+    ) -> ExplorationReport {
+        let mut new_corpus = vec![];
+        let mut failures = vec![];
+
+        for run in 0..iterations {
+            let config = if run == 0 {
+                self.base.clone()
+            } else {
+                let parent = self.corpus.choose(&mut self.rng).unwrap().clone();
+                self.mutate(parent)
+            };
+
+            take_coverage();
+            let finished = simulate_with_config(&config, &mut simulate);
+            let coverage = take_coverage();
+
+            let new_points = coverage.difference(&self.coverage).count();
+            if new_points > 0 {
+                info!(
+                    run = run,
+                    seed = config.seed,
+                    new_points = new_points,
+                    "Reached new coverage"
+                );
+                self.coverage.extend(coverage);
+                self.corpus.push(config.clone());
+                new_corpus.push(config.clone());
+            }
+            if !finished {
+                failures.push(config);
+            }
+        }
+
+        ExplorationReport {
+            runs: iterations,
+            coverage: self.coverage.clone(),
+            corpus: new_corpus,
+            failures,
+        }
+    }
+
+    fn mutate(&mut self, mut config: Configuration) -> Configuration {
+        let rng = &mut self.rng;
+        config.seed = rng.next_u64();
+
+        // Perturb some of the fault parameters. Faults can also be switched on
+        // and off, using the base configuration when switching them on.
+        let network = &mut config.network_config;
+        let base_network = &self.base.network_config;
+        if rng.gen_bool(0.3) {
+            network.mean_time_between_link_failures = mutate_mean_time(
+                network.mean_time_between_link_failures,
+                base_network.mean_time_between_link_failures,
+                rng,
+            );
+        }
+        if rng.gen_bool(0.3) {
+            network.mean_link_recovery_time = scale(network.mean_link_recovery_time, rng);
+        }
+        if rng.gen_bool(0.3) {
+            network.mean_time_between_partitions = mutate_mean_time(
+                network.mean_time_between_partitions,
+                base_network.mean_time_between_partitions,
+                rng,
+            );
+        }
+        if rng.gen_bool(0.3) {
+            network.mean_partition_recovery_time = scale(network.mean_partition_recovery_time, rng);
+        }
+        if rng.gen_bool(0.3) {
+            network.duplicate_probability = rng.gen_range(0.0..0.5);
+        }
+        if rng.gen_bool(0.3) {
+            network.hold_probability = rng.gen_range(0.0..1.0);
+        }
+
+        let failure = &mut config.failure_config;
+        if rng.gen_bool(0.3) {
+            failure.mean_time_between_failures = mutate_mean_time(
+                failure.mean_time_between_failures,
+                self.base.failure_config.mean_time_between_failures,
+                rng,
+            );
+        }
+        if rng.gen_bool(0.3) {
+            failure.mean_time_to_recover = scale(failure.mean_time_to_recover, rng);
+        }
+
+        config
+    }
+}
+
+fn mutate_mean_time(
+    mean_time: Option<Duration>,
+    base: Option<Duration>,
+    rng: &mut dyn RngCore,
+) -> Option<Duration> {
+    if rng.gen_bool(0.2) {
+        return match mean_time {
+            Some(_) => None,
+            None => base.or(Some(Duration::from_secs(1))),
+        };
+    }
+    mean_time.map(|mean_time| scale(mean_time, rng))
+}
+
+// Scales a (non-zero) duration by a random factor between 0.5 and 2.
+fn scale(duration: Duration, rng: &mut dyn RngCore) -> Duration {
+    duration
+        .mul_f64(rng.gen_range(0.5..2.0))
+        .max(Duration::from_millis(1))
+}
@@ -0,0 +1,34 @@
+mod coverage;
+mod sweep;
+
+pub use coverage::*;
+pub use sweep::*;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use tracing::error;
+
+use crate::Configuration;
+
+// Runs a simulation for the configuration. If an invariant is violated, the
+// configuration is logged before the panic is propagated, so the failure can be
+// reproduced. With the serde feature, it is logged as TOML that can be loaded
+// with `Configuration::from_toml`.
+fn simulate_with_config(
+    config: &Configuration,
+    simulate: &mut impl FnMut(Configuration) -> bool,
+) -> bool {
+    match panic::catch_unwind(AssertUnwindSafe(|| simulate(config.clone()))) {
+        Ok(finished) => finished,
+        Err(violation) => {
+            #[cfg(feature = "serde")]
+            match config.to_toml() {
+                Ok(toml) => error!(config = %toml, "Invariant violated"),
+                Err(_) => error!(config = ?config, "Invariant violated"),
+            }
+            #[cfg(not(feature = "serde"))]
+            error!(config = ?config, "Invariant violated");
+            panic::resume_unwind(violation);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use tracing::info;
+
+use std::{ops::Range, time::Duration};
+
+use crate::Configuration;
+
+use super::simulate_with_config;
+
+/// Runs a simulation for every seed in a range.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    base: Configuration,
+    seeds: Range<u64>,
+    swarm: bool,
+}
+
+/// Result of a sweep.
+#[derive(Debug, Clone)]
+pub struct SweepReport {
+    /// Number of simulations that were run.
+    pub runs: usize,
+    /// Exact configurations (including the seed) for which the simulation did
+    /// not finish.
+    pub failures: Vec<Configuration>,
+}
+
+impl Sweep {
+    pub fn new(base: Configuration, seeds: Range<u64>) -> Self {
+        Sweep {
+            base,
+            seeds,
+            swarm: false,
+        }
+    }
+
+    /// Enables swarm testing: instead of using the same faults for every seed,
+    /// a random subset of fault types and their intensity is derived from each
+    /// seed (see [`swarm_config`]).
+    pub fn swarm(mut self) -> Self {
+        self.swarm = true;
+        self
+    }
+
+    /// Returns the configuration used for the given seed.
+    pub fn config(&self, seed: u64) -> Configuration {
+        if self.swarm {
+            swarm_config(&self.base, seed)
+        } else {
+            Configuration {
+                seed,
+                ..self.base.clone()
+            }
+        }
+    }
+
+    /// Runs the simulations. The closure runs a simulation for a configuration
+    /// and returns whether it finished (see [`crate::Simulator::run`]).
+    /// Invariant violations panic after logging the configuration that caused
+    /// them.
+    pub fn run(&self, mut simulate: impl FnMut(Configuration) -> bool) -> SweepReport {
+        let mut failures = vec![];
+        for seed in self.seeds.clone() {
+            let config = self.config(seed);
+            if !simulate_with_config(&config, &mut simulate) {
+                info!(config = ?config, "Simulation did not finish");
+                failures.push(config);
+            }
+        }
+
+        SweepReport {
+            runs: self.seeds.clone().count(),
+            failures,
+        }
+    }
+}
+
+/// Derives a configuration with a random subset of fault types and random
+/// fault intensities from the seed ("swarm testing"). Disabling some fault
+/// types per run tends to find more bugs than always enabling all of them.
+/// The derivation is deterministic, so the seed is enough to reproduce it.
+pub fn swarm_config(base: &Configuration, seed: u64) -> Configuration {
+    // use a separate stream so the derivation does not correlate with the
+    // randomness of the simulation itself
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+
+    let mut config = base.clone();
+    config.seed = seed;
+
+    let network = &mut config.network_config;
+    network.duplicate_probability = if rng.gen_bool(0.5) {
+        rng.gen_range(0.01..0.3)
+    } else {
+        0.0
+    };
+    network.mean_time_between_link_failures =
+        rng.gen_bool(0.5).then(|| millis(&mut rng, 200..5_000));
+    network.mean_link_recovery_time = millis(&mut rng, 50..1_000);
+    network.hold_probability = rng.gen_range(0.0..1.0);
+    network.mean_time_between_partitions = rng.gen_bool(0.5).then(|| millis(&mut rng, 500..10_000));
+    network.mean_partition_recovery_time = millis(&mut rng, 100..3_000);
+    network.min_message_latency = millis(&mut rng, 0..50);
+    network.max_message_latency = network.min_message_latency + millis(&mut rng, 1..500);
+
+    let failure = &mut config.failure_config;
+    failure.mean_time_between_failures = rng.gen_bool(0.5).then(|| millis(&mut rng, 500..10_000));
+    failure.mean_time_to_recover = millis(&mut rng, 100..3_000);
+
+    config
+}
+
+fn millis(rng: &mut ChaCha8Rng, range: Range<u64>) -> Duration {
+    Duration::from_millis(rng.gen_range(range))
+}
@@ -0,0 +1,94 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+/// Distribution of simulated latencies. All samples are kept (as counts per
+/// distinct latency), so percentiles are exact and deterministic for a seed.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct LatencyHistogram {
+    counts: BTreeMap<Duration, usize>,
+    count: usize,
+    total: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        *self.counts.entry(latency).or_default() += 1;
+        self.count += 1;
+        self.total += latency;
+    }
+
+    /// Adds all samples of the other histogram.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (latency, count) in &other.counts {
+            *self.counts.entry(*latency).or_default() += count;
+        }
+        self.count += other.count;
+        self.total += other.total;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.counts.keys().next().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.counts.keys().next_back().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (!self.is_empty()).then(|| self.total / self.count as u32)
+    }
+
+    /// Returns the latency below or at which the given percentage (between 0
+    /// and 100) of the samples lie, using the nearest-rank method.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as usize;
+        let mut seen = 0;
+        self.counts.iter().find_map(|(latency, count)| {
+            seen += count;
+            (seen >= rank).then_some(*latency)
+        })
+    }
+
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+}
+
+/// Summarizes the histogram as `count=.. p50=.. p95=.. p99=.. max=..`.
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(p50), Some(p95), Some(p99), Some(max)) =
+            (self.p50(), self.p95(), self.p99(), self.max())
+        else {
+            return write!(f, "count=0");
+        };
+        write!(
+            f,
+            "count={} p50={:?} p95={:?} p99={:?} max={:?}",
+            self.count(),
+            p50,
+            p95,
+            p99,
+            max
+        )
+    }
+}
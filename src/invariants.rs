@@ -0,0 +1,121 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    util::panic_message, DeterministicClient, DeterministicNode, InvariantChecker, SimulationView,
+};
+
+/// When an additional invariant checker runs, see
+/// [`crate::Simulator::add_invariant_checker`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CheckSchedule {
+    /// After every n-th processed event.
+    EveryEvents(usize),
+    /// After the first event once the given simulated time has passed since
+    /// the last check.
+    EveryTime(Duration),
+    /// Whenever the last message in flight has been delivered or dropped.
+    OnQuiescence,
+    /// Once, when the simulation stops because the clients finished (and the
+    /// simulation settled, if configured), the simulation timed out or no
+    /// events are left.
+    AtEnd,
+}
+
+impl CheckSchedule {
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            CheckSchedule::EveryEvents(count) => count > 0,
+            CheckSchedule::EveryTime(period) => !period.is_zero(),
+            CheckSchedule::OnQuiescence | CheckSchedule::AtEnd => true,
+        }
+    }
+}
+
+/// An invariant checker with a name for failure reports and its own schedule.
+pub(crate) struct ScheduledChecker<
+    N: DeterministicNode,
+    C: DeterministicClient<Message = N::Message>,
+> {
+    pub(crate) name: String,
+    pub(crate) schedule: CheckSchedule,
+    checker: Rc<dyn InvariantChecker<N, C>>,
+    last_check: Duration,
+}
+
+// Checkers are shared between forks, they only see the state passed to them.
+impl<N: DeterministicNode, C: DeterministicClient<Message = N::Message>> Clone
+    for ScheduledChecker<N, C>
+{
+    fn clone(&self) -> Self {
+        ScheduledChecker {
+            name: self.name.clone(),
+            schedule: self.schedule,
+            checker: self.checker.clone(),
+            last_check: self.last_check,
+        }
+    }
+}
+
+impl<N: DeterministicNode, C: DeterministicClient<Message = N::Message>> ScheduledChecker<N, C> {
+    pub(crate) fn new(
+        name: String,
+        schedule: CheckSchedule,
+        checker: Rc<dyn InvariantChecker<N, C>>,
+    ) -> Self {
+        ScheduledChecker {
+            name,
+            schedule,
+            checker,
+            last_check: Duration::ZERO,
+        }
+    }
+
+    /// Returns whether the checker is due after an event, given the number of
+    /// processed events, the simulated time and whether the simulation just
+    /// became quiescent.
+    pub(crate) fn is_due(
+        &mut self,
+        event_count: usize,
+        elapsed: Duration,
+        became_quiescent: bool,
+    ) -> bool {
+        match self.schedule {
+            CheckSchedule::EveryEvents(count) => event_count.is_multiple_of(count),
+            CheckSchedule::EveryTime(period) => {
+                let is_due = elapsed >= self.last_check + period;
+                if is_due {
+                    self.last_check = elapsed;
+                }
+                is_due
+            }
+            CheckSchedule::OnQuiescence => became_quiescent,
+            CheckSchedule::AtEnd => false,
+        }
+    }
+
+    /// Returns whether the checker is due when the simulation stops.
+    pub(crate) fn is_due_at_end(&self) -> bool {
+        self.schedule == CheckSchedule::AtEnd
+    }
+
+    /// Runs the checker. A violation is reported with the name of the checker
+    /// and the simulated time.
+    pub(crate) fn check(&self, view: &SimulationView<'_, N, C>) {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.checker.check_view(view)));
+
+        if let Err(violation) = result {
+            let message = panic_message(&*violation);
+            panic!(
+                "Invariant checker '{}' failed at {:?} (seed {}): {}",
+                self.name,
+                view.time(),
+                view.seed(),
+                message
+            );
+        }
+    }
+}
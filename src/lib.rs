@@ -1,13 +1,51 @@
+mod builder;
+mod checkers;
+mod client;
 mod config;
+#[cfg(feature = "serde")]
+mod config_file;
+mod exploration;
+mod histogram;
+mod invariants;
 mod model;
+mod model_checker;
 mod networking;
 mod node;
+mod observer;
+mod presets;
+mod scheduler;
 mod simulator;
+mod statistics;
 mod tests;
+mod trace;
 mod util;
+mod view;
+mod workload;
 
-pub use config::{Configuration, FailureConfiguration};
+pub use builder::{BuildError, SimulatorBuilder};
+pub use checkers::*;
+pub use client::Client;
+pub use config::{
+    Configuration, ConfigurationError, FailureConfiguration, InvalidField, ProcessingConfiguration,
+    SettleConfiguration,
+};
+#[cfg(feature = "serde")]
+pub use config_file::ConfigFileError;
+pub use exploration::*;
+pub use histogram::LatencyHistogram;
+pub use invariants::CheckSchedule;
 pub use model::*;
+pub use model_checker::{Choice, ModelCheckResult, ModelChecker, ModelCheckerConfig};
 pub use networking::*;
 pub use node::{Node, NodeId};
-pub use simulator::Simulator;
+pub use observer::SimulationObserver;
+pub use scheduler::{PctConfiguration, SchedulingStrategy};
+pub use simulator::{
+    Event, NodeFactory, ProcessedEvent, SimulationMessage, Simulator, StepResult, StopReason,
+};
+pub use statistics::SimulationStatistics;
+pub use trace::*;
+pub use view::{PendingDelivery, SimulationView};
+pub use workload::{
+    Arrivals, Backoff, OperationMix, Reply, Workload, WorkloadClient, WorkloadConfig,
+};
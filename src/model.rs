@@ -1,6 +1,10 @@
-use std::{fmt::Debug, time::Instant};
+use std::{
+    error::Error,
+    fmt::{self, Debug},
+    time::{Duration, Instant},
+};
 
-use crate::{node::NodeId, Node};
+use crate::{node::NodeId, Client, Node, SimulationView};
 
 pub trait ProtocolMessage: Clone + Debug + Eq + PartialEq {
     /// Returns the source of the message.
@@ -31,8 +35,61 @@ pub trait DeterministicNode: Debug {
     /// Returns whether the node is currently recovering (the node is not yet
     /// ready to process messages).
     fn is_recovering(&self) -> bool;
+
+    /// Returns the simulated time it takes to process the given message. If
+    /// None, the processing time is sampled from the configuration.
+    fn message_processing_time(&self, _msg: &Self::Message) -> Option<Duration> {
+        None
+    }
+
+    /// Returns the simulated time it takes to perform a tick. If None, the
+    /// tick takes no time.
+    fn tick_processing_time(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns membership changes the node requests, e.g., as part of a
+    /// reconfiguration protocol. Called after every message or tick the node
+    /// handled. Spawning nodes requires a node factory on the simulator.
+    fn membership_changes(&mut self) -> Vec<MembershipChange> {
+        vec![]
+    }
+}
+
+/// A change of the set of nodes participating in the simulation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MembershipChange {
+    /// Adds a new node with a fresh id.
+    Spawn,
+    /// Permanently removes the node.
+    Decommission(NodeId),
+}
+
+/// Error when a membership change cannot be applied. Changes requested by
+/// nodes are logged and ignored if they fail.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MembershipError {
+    /// Only nodes can be removed, not clients.
+    NotANode(NodeId),
+    UnknownNode(NodeId),
+    /// Spawning a node requires a node factory on the simulator.
+    MissingNodeFactory,
+}
+
+impl fmt::Display for MembershipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MembershipError::NotANode(id) => write!(f, "Only nodes can be removed, got {}", id),
+            MembershipError::UnknownNode(id) => write!(f, "Unknown node {}", id),
+            MembershipError::MissingNodeFactory => {
+                write!(f, "A node factory is required to spawn nodes")
+            }
+        }
+    }
 }
 
+impl Error for MembershipError {}
+
 pub trait DeterministicClient: Debug {
     type Message: ProtocolMessage;
 
@@ -50,12 +107,51 @@ pub trait DeterministicClient: Debug {
     /// Returns whether the client has finished its work, e.g., all requests have
     /// been processed.
     fn finished(&self) -> bool;
+
+    /// Restarts the client after a crash, e.g., by opening a new session.
+    /// Only called if a client failure configuration is given.
+    fn recover(&mut self, _now: Instant, _nonce: u64) {}
+
+    /// Returns the requests the client started or completed since the last
+    /// call. The simulator measures their latencies in simulated time (see
+    /// [`crate::Simulator::statistics`]). Called after every message or tick
+    /// the client handled.
+    fn request_marks(&mut self) -> Vec<RequestMark> {
+        vec![]
+    }
+}
+
+/// Marks the start or end of a client request, identified by an id that is
+/// unique per client.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RequestMark {
+    Start(u64),
+    End(u64),
+    /// The request was given up or failed and is not measured.
+    Cancel(u64),
 }
 
 pub trait InvariantChecker<N: DeterministicNode, C: DeterministicClient<Message = N::Message>> {
     /// Checks invariants of the system given the current state, e.g., an
     /// acknowledged message must be replicated to a majority of nodes in
     /// a consensus protocol. The invariants are checked periodically during
-    /// simulation.
-    fn check_invariants(&self, seed: u64, nodes: &[Node<N>], clients: &[C]);
+    /// simulation. It is only called by the default [`Self::check_view`], so
+    /// checkers that override `check_view` implement it as `unreachable!()`.
+    fn check_invariants(&self, seed: u64, nodes: &[Node<N>], clients: &[Client<C>]);
+
+    /// Checks invariants that also depend on the network, e.g., at most one
+    /// message of a kind in flight, or leaders must agree if the network is
+    /// not partitioned and all nodes are up. This is what the simulator calls,
+    /// by default it checks the invariants of the nodes and clients only.
+    fn check_view(&self, view: &SimulationView<'_, N, C>) {
+        self.check_invariants(view.seed(), view.nodes(), view.clients());
+    }
+}
+
+/// Checks nothing, for simulations that only use checkers added with
+/// [`crate::Simulator::add_invariant_checker`].
+impl<N: DeterministicNode, C: DeterministicClient<Message = N::Message>> InvariantChecker<N, C>
+    for ()
+{
+    fn check_invariants(&self, _seed: u64, _nodes: &[Node<N>], _clients: &[Client<C>]) {}
 }
@@ -0,0 +1,376 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use tracing::{error, info};
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    node::{Node, NodeId},
+    simulator::validate_node_ids,
+    util::panic_message,
+    Client, DeterministicClient, DeterministicNode, FailureConfiguration, InvariantChecker,
+    ProtocolMessage, SimulationView,
+};
+
+/// Bounds of the exhaustive exploration.
+#[derive(Debug, Clone)]
+pub struct ModelCheckerConfig {
+    /// Maximum number of choices along a path from the initial state.
+    pub max_depth: usize,
+    /// Maximum number of distinct states to explore. None means unbounded.
+    pub max_states: Option<usize>,
+    /// Maximum number of messages dropped along a path.
+    pub max_drops: usize,
+    /// Maximum number of messages duplicated along a path.
+    pub max_duplicates: usize,
+    /// Maximum number of node crashes along a path.
+    pub max_crashes: usize,
+    /// Simulated time that passes with every tick.
+    pub tick_interval: Duration,
+}
+
+impl Default for ModelCheckerConfig {
+    fn default() -> Self {
+        ModelCheckerConfig {
+            max_depth: 20,
+            max_states: Some(1_000_000),
+            max_drops: 1,
+            max_duplicates: 1,
+            max_crashes: 1,
+            tick_interval: Duration::from_millis(50),
+        }
+    }
+}
+
+/// A choice point of the exploration.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Choice<M> {
+    Deliver(M),
+    Drop(M),
+    Duplicate(M),
+    Crash(NodeId),
+    Restart(NodeId),
+    /// Advances the time by the tick interval and ticks all nodes and clients.
+    Tick,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ModelCheckResult {
+    /// Number of distinct states that were explored.
+    pub states_explored: usize,
+    /// Whether all states within the bounds were explored, i.e., no path was
+    /// cut off by the depth or state limit.
+    pub exhaustive: bool,
+}
+
+#[derive(Clone)]
+struct State<N: DeterministicNode, C: DeterministicClient<Message = N::Message>> {
+    nodes: Vec<Node<N>>,
+    clients: Vec<Client<C>>,
+    in_flight: Vec<N::Message>,
+    now: Instant,
+    drops: usize,
+    duplicates: usize,
+    crashes: usize,
+}
+
+/// Explores all interleavings of message deliveries, drops, duplicates, node
+/// crashes and restarts of a small configuration up to a bounded depth. States
+/// are deduplicated by hashing and the invariants are checked in every state.
+/// Unlike the [`crate::Simulator`], messages are delivered without latency and
+/// time only advances with ticks. States that only differ in the time are
+/// considered the same, so nodes and clients that act on time must keep the
+/// relevant times in their (hashed) state. Membership changes requested by
+/// nodes are ignored.
+pub struct ModelChecker<
+    N: DeterministicNode,
+    C: DeterministicClient<Message = N::Message>,
+    I: InvariantChecker<N, C>,
+> {
+    start_time: Instant,
+    nodes: Vec<N>,
+    clients: Vec<C>,
+    config: ModelCheckerConfig,
+    invariant_checker: I,
+}
+
+impl<N, C, I> ModelChecker<N, C, I>
+where
+    N: DeterministicNode + Clone + Hash,
+    C: DeterministicClient<Message = N::Message> + Clone + Hash,
+    N::Message: Hash,
+    I: InvariantChecker<N, C>,
+{
+    pub fn new(
+        start_time: Instant,
+        nodes: Vec<N>,
+        clients: Vec<C>,
+        config: ModelCheckerConfig,
+        invariant_checker: I,
+    ) -> Self {
+        if let Err(error) = validate_node_ids(&nodes, &clients) {
+            panic!("{}", error);
+        }
+
+        ModelChecker {
+            start_time,
+            nodes,
+            clients,
+            config,
+            invariant_checker,
+        }
+    }
+
+    /// Explores the state space. Invariant violations panic (as in the
+    /// simulator) with the choices that lead to the violating state.
+    /// The seed passed to the invariant checker is always 0.
+    pub fn run(&self) -> ModelCheckResult {
+        let initial = self.initial_state();
+        let mut seen = HashSet::from([fingerprint(&initial)]);
+        // choices leading to each explored state as (parent, choice) pairs
+        let mut paths: Vec<(usize, Option<Choice<N::Message>>)> = vec![(0, None)];
+        self.check_invariants(&initial, 0, &paths);
+
+        let mut queue = VecDeque::from([(initial, 0, 0)]);
+        let mut exhaustive = true;
+        'explore: while let Some((state, path, depth)) = queue.pop_front() {
+            if state.clients.iter().all(|client| client.finished()) {
+                continue;
+            }
+            if depth >= self.config.max_depth {
+                exhaustive = false;
+                continue;
+            }
+
+            for choice in self.choices(&state) {
+                let mut next = state.clone();
+                self.apply(&mut next, &choice);
+                if !seen.insert(fingerprint(&next)) {
+                    continue;
+                }
+
+                paths.push((path, Some(choice)));
+                self.check_invariants(&next, paths.len() - 1, &paths);
+                queue.push_back((next, paths.len() - 1, depth + 1));
+
+                if self
+                    .config
+                    .max_states
+                    .is_some_and(|max_states| seen.len() >= max_states)
+                {
+                    exhaustive = false;
+                    break 'explore;
+                }
+            }
+        }
+
+        info!(
+            states_explored = seen.len(),
+            exhaustive = exhaustive,
+            "Model checking finished"
+        );
+        ModelCheckResult {
+            states_explored: seen.len(),
+            exhaustive,
+        }
+    }
+
+    fn initial_state(&self) -> State<N, C> {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        // nodes never fail by themselves, crashes are explicit choices
+        let failure_config = FailureConfiguration {
+            mean_time_between_failures: None,
+            ..FailureConfiguration::default()
+        };
+        let replica_count = self.nodes.len();
+        State {
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| {
+                    Node::new(
+                        node.clone(),
+                        failure_config.clone(),
+                        &mut rng,
+                        self.start_time,
+                        self.start_time,
+                        replica_count,
+                    )
+                })
+                .collect(),
+            clients: self
+                .clients
+                .iter()
+                .map(|client| Client::new(client.clone(), None, &mut rng, self.start_time))
+                .collect(),
+            in_flight: vec![],
+            now: self.start_time,
+            drops: 0,
+            duplicates: 0,
+            crashes: 0,
+        }
+    }
+
+    fn choices(&self, state: &State<N, C>) -> Vec<Choice<N::Message>> {
+        let mut choices = vec![];
+
+        let mut distinct_messages: Vec<&N::Message> = vec![];
+        for msg in &state.in_flight {
+            if !distinct_messages.contains(&msg) {
+                distinct_messages.push(msg);
+            }
+        }
+        for msg in distinct_messages {
+            choices.push(Choice::Deliver(msg.clone()));
+            if state.drops < self.config.max_drops {
+                choices.push(Choice::Drop(msg.clone()));
+            }
+            if state.duplicates < self.config.max_duplicates {
+                choices.push(Choice::Duplicate(msg.clone()));
+            }
+        }
+
+        // as in the simulator, never more than a minority of nodes is down
+        let max_failures = state.nodes.len() / 2;
+        let currently_failed = state.nodes.iter().filter(|n| !n.is_up()).count();
+        for node in &state.nodes {
+            if node.is_crashed() {
+                choices.push(Choice::Restart(node.id()));
+            } else if state.crashes < self.config.max_crashes && currently_failed < max_failures {
+                choices.push(Choice::Crash(node.id()));
+            }
+        }
+
+        choices.push(Choice::Tick);
+        choices
+    }
+
+    fn apply(&self, state: &mut State<N, C>, choice: &Choice<N::Message>) {
+        // nodes and clients never fail by themselves, so randomness is unused
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let now = state.now;
+        match choice {
+            Choice::Deliver(msg) => {
+                remove_message(&mut state.in_flight, msg);
+                let messages = match msg.destination() {
+                    NodeId::Node(node_id) => {
+                        let node = &mut state.nodes[node_id];
+                        if node.is_crashed() {
+                            vec![]
+                        } else {
+                            node.process_message(msg.clone(), now, false, &mut rng)
+                        }
+                    }
+                    NodeId::Client(client_id) => {
+                        state.clients[client_id].process_message(msg.clone(), now, &mut rng)
+                    }
+                };
+                state.in_flight.extend(messages);
+            }
+            Choice::Drop(msg) => {
+                remove_message(&mut state.in_flight, msg);
+                state.drops += 1;
+            }
+            Choice::Duplicate(msg) => {
+                state.in_flight.push(msg.clone());
+                state.duplicates += 1;
+            }
+            Choice::Crash(NodeId::Node(node_id)) => {
+                state.nodes[*node_id].crash(now);
+                state.crashes += 1;
+            }
+            Choice::Restart(NodeId::Node(node_id)) => {
+                state.nodes[*node_id].restart(now, state.crashes as u64);
+            }
+            Choice::Crash(_) | Choice::Restart(_) => unreachable!("Only nodes crash"),
+            Choice::Tick => {
+                state.now += self.config.tick_interval;
+                let now = state.now;
+                for node in &mut state.nodes {
+                    if !node.is_crashed() {
+                        state.in_flight.extend(node.tick(now, &mut rng));
+                    }
+                }
+                for client in &mut state.clients {
+                    state.in_flight.extend(client.tick(now, &mut rng));
+                }
+            }
+        }
+    }
+
+    fn check_invariants(
+        &self,
+        state: &State<N, C>,
+        path: usize,
+        paths: &[(usize, Option<Choice<N::Message>>)],
+    ) {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.invariant_checker
+                .check_view(&SimulationView::for_model_checker(
+                    state.now.duration_since(self.start_time),
+                    &state.nodes,
+                    &state.clients,
+                    &state.in_flight,
+                ))
+        }));
+
+        if let Err(violation) = result {
+            let mut choices = vec![];
+            let mut current = path;
+            while let (parent, Some(choice)) = &paths[current] {
+                choices.push(choice);
+                current = *parent;
+            }
+            choices.reverse();
+            error!(choices = ?choices, "Invariant violated");
+            let message = panic_message(&*violation);
+            panic!(
+                "Invariant violated after choices {:?}: {}",
+                choices, message
+            );
+        }
+    }
+}
+
+fn remove_message<M: ProtocolMessage>(in_flight: &mut Vec<M>, msg: &M) {
+    if let Some(pos) = in_flight.iter().position(|m| m == msg) {
+        in_flight.remove(pos);
+    }
+}
+
+// Hash of a state. In-flight messages are hashed as a multiset.
+fn fingerprint<N, C>(state: &State<N, C>) -> u64
+where
+    N: DeterministicNode + Hash,
+    C: DeterministicClient<Message = N::Message> + Hash,
+    N::Message: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+    for node in &state.nodes {
+        node.node().hash(&mut hasher);
+        node.is_crashed().hash(&mut hasher);
+    }
+    for client in &state.clients {
+        client.client().hash(&mut hasher);
+    }
+
+    let mut messages = state
+        .in_flight
+        .iter()
+        .map(|msg| {
+            let mut hasher = DefaultHasher::new();
+            msg.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect::<Vec<_>>();
+    messages.sort_unstable();
+    messages.hash(&mut hasher);
+
+    (state.drops, state.duplicates, state.crashes).hash(&mut hasher);
+    hasher.finish()
+}
@@ -1,25 +1,115 @@
 use std::time::Duration;
 
-use rand_distr::Exp;
+use rand::RngCore;
+use rand_distr::{Distribution, Exp, Normal};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct NetworkConfig {
     // Latency configuration
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub min_message_latency: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub max_message_latency: Duration,
-    pub latency_distribution: Exp<f64>,
+    pub latency_distribution: LatencyDistribution,
 
     // Duplicate configuration
     pub duplicate_probability: f64,
 
     // Failure configuration
     pub hold_probability: f64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::config_file::optional_duration")
+    )]
     pub mean_time_between_link_failures: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub mean_link_recovery_time: Duration,
 
     // Partition configuration
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::config_file::optional_duration")
+    )]
     pub mean_time_between_partitions: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config_file::duration"))]
     pub mean_partition_recovery_time: Duration,
+
+    // Queue configuration. The capacity bounds both the messages held back by
+    // a link (while it is in the hold state) and the messages in flight on a
+    // link, separately for each direction. None means unbounded.
+    pub link_queue_capacity: Option<usize>,
+    pub queue_overflow_policy: OverflowPolicy,
+}
+
+/// Determines which message is dropped when a link queue is full.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OverflowPolicy {
+    /// The newly sent message is dropped.
+    #[default]
+    DropTail,
+    /// The oldest queued message is dropped to make room for the new one.
+    DropHead,
+}
+
+/// Distribution of message latencies. Samples are fractions of the range
+/// between the minimum and maximum latency and are capped at the maximum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)
+)]
+pub enum LatencyDistribution {
+    /// Exponential distribution with the given rate. The higher the rate, the
+    /// more messages arrive close to the minimum latency.
+    Exponential { rate: f64 },
+    /// All latencies in the range are equally likely.
+    Uniform,
+    /// Normal distribution, truncated to the range.
+    Normal { mean: f64, std_dev: f64 },
+}
+
+impl Default for LatencyDistribution {
+    fn default() -> Self {
+        LatencyDistribution::Exponential { rate: 5.0 }
+    }
+}
+
+impl LatencyDistribution {
+    pub(crate) fn sample(&self, rand: &mut dyn RngCore) -> f64 {
+        match *self {
+            LatencyDistribution::Exponential { rate } => Exp::new(rate).unwrap().sample(rand),
+            LatencyDistribution::Uniform => {
+                rand_distr::Uniform::new_inclusive(0.0, 1.0).sample(rand)
+            }
+            LatencyDistribution::Normal { mean, std_dev } => {
+                Normal::new(mean, std_dev).unwrap().sample(rand).max(0.0)
+            }
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Returns the configuration without link failures, partitions and
+    /// duplicates. Latencies and queues stay the same.
+    pub(crate) fn without_faults(&self) -> Self {
+        NetworkConfig {
+            duplicate_probability: 0.0,
+            mean_time_between_link_failures: None,
+            mean_time_between_partitions: None,
+            ..self.clone()
+        }
+    }
 }
 
 impl Default for NetworkConfig {
@@ -27,13 +117,15 @@ impl Default for NetworkConfig {
         NetworkConfig {
             min_message_latency: Duration::from_millis(0),
             max_message_latency: Duration::from_millis(100),
-            latency_distribution: Exp::new(5.0).unwrap(),
+            latency_distribution: LatencyDistribution::default(),
             duplicate_probability: 0.1,
             mean_time_between_link_failures: Some(Duration::from_millis(1000)),
             mean_link_recovery_time: Duration::from_millis(300),
             hold_probability: 0.3, // 30% chance of temporary failures hold and then recover
             mean_time_between_partitions: Some(Duration::from_millis(4000)),
             mean_partition_recovery_time: Duration::from_millis(1000),
+            link_queue_capacity: None,
+            queue_overflow_policy: OverflowPolicy::DropTail,
         }
     }
 }
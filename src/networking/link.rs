@@ -1,16 +1,19 @@
-use rand::prelude::*;
 use rand::{Rng, RngCore};
-use tracing::info;
+use tracing::{debug, info};
 
 use std::{
+    collections::{HashMap, VecDeque},
     rc::Rc,
     time::{Duration, Instant},
 };
 
 use crate::node::NodeId;
+use crate::trace::{DropReason, LinkStatus, TraceEvent};
 use crate::util::sample_failure_time;
+use crate::ProtocolMessage;
 
-use super::{DeliverMessage, NetworkConfig};
+use super::network::DeliveryTracker;
+use super::{DeliverMessage, NetworkConfig, OverflowPolicy};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum LinkState<M> {
@@ -22,19 +25,48 @@ pub enum LinkState<M> {
     },
     TempHold {
         expected_recovery: Instant,
-        queued_messages: Vec<M>,
+        /// Held messages with their message ids.
+        queued_messages: VecDeque<(usize, M)>,
     },
 }
 
+/// Delay added to the latency of a message by a rule, see
+/// [`super::RuleAction`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct ExtraDelay {
+    pub(super) delay: Duration,
+    pub(super) not_before: Option<Instant>,
+}
+
+impl ExtraDelay {
+    fn apply(&self, now: Instant, latency: Duration) -> Duration {
+        let latency = latency + self.delay;
+        match self.not_before {
+            Some(not_before) => std::cmp::max(latency, not_before.saturating_duration_since(now)),
+            None => latency,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct InFlight {
+    delivery_time: Instant,
+    delivery_id: u64,
+}
+
+#[derive(Clone)]
 pub struct Link<M> {
     state: LinkState<M>,
     config: Rc<NetworkConfig>,
     simulation_start: Instant,
     from: NodeId,
     to: NodeId,
+    // Messages sent on this link that have not been delivered yet, by
+    // destination and in the order they were sent.
+    in_flight: HashMap<NodeId, VecDeque<InFlight>>,
 }
 
-impl<M: Clone + std::fmt::Debug> Link<M> {
+impl<M: ProtocolMessage> Link<M> {
     pub fn new(
         config: Rc<NetworkConfig>,
         simulation_start: Instant,
@@ -49,9 +81,63 @@ impl<M: Clone + std::fmt::Debug> Link<M> {
             simulation_start,
             from,
             to,
+            in_flight: HashMap::new(),
         }
     }
 
+    /// Returns the state of the link as of the last message sent on it.
+    pub(super) fn state(&self) -> &LinkState<M> {
+        &self.state
+    }
+
+    /// Brings the link up for good and puts the messages it held back in
+    /// flight.
+    pub(super) fn heal(
+        &mut self,
+        now: Instant,
+        config: Rc<NetworkConfig>,
+        rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
+    ) -> Vec<DeliverMessage<M>> {
+        self.config = config;
+        let state = std::mem::replace(
+            &mut self.state,
+            LinkState::Up {
+                expected_failure: None,
+            },
+        );
+        if !matches!(state, LinkState::Up { .. }) {
+            info!(
+                time = ?now.duration_since(self.simulation_start),
+                from = ?self.from,
+                to = ?self.to,
+                "Link healed"
+            );
+            deliveries.events.push(TraceEvent::LinkChange {
+                from: self.from,
+                to: self.to,
+                status: LinkStatus::Up,
+            });
+        }
+        let LinkState::TempHold {
+            queued_messages, ..
+        } = state
+        else {
+            return vec![];
+        };
+        queued_messages
+            .into_iter()
+            .filter_map(|(message_id, m)| {
+                self.admit(m, message_id, ExtraDelay::default(), now, rand, deliveries)
+            })
+            .collect()
+    }
+
+    /// Returns whether the link holds back messages until it recovers.
+    pub(super) fn has_held_messages(&self) -> bool {
+        matches!(&self.state, LinkState::TempHold { queued_messages, .. } if !queued_messages.is_empty())
+    }
+
     pub fn gen_up_state(
         now: Instant,
         rand: &mut dyn RngCore,
@@ -68,91 +154,192 @@ impl<M: Clone + std::fmt::Debug> Link<M> {
         }
     }
 
-    pub fn send(
+    /// Sends a message whose latency is extended by the given delay. Messages
+    /// the link holds back while it is down are delivered without the extra
+    /// delay once it recovers.
+    pub(super) fn send(
         &mut self,
         message: M,
+        message_id: usize,
+        extra_delay: ExtraDelay,
         now: Instant,
         rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
     ) -> Vec<DeliverMessage<M>> {
-        let mut released_messages = self.check_state_transition(now, rand);
+        let released_messages = self.check_state_transition(now, rand, deliveries);
+        for in_flight in self.in_flight.values_mut() {
+            in_flight.retain(|m| m.delivery_time > now);
+        }
 
         match &mut self.state {
             LinkState::Up { .. } => {
+                let mut messages = released_messages
+                    .into_iter()
+                    .map(|(id, m)| (id, m, ExtraDelay::default()))
+                    .collect::<Vec<_>>();
                 if rand.gen_bool(self.config.duplicate_probability) {
-                    released_messages.push(message.clone());
+                    deliveries.events.push(TraceEvent::Duplicate {
+                        message: message.clone(),
+                    });
+                    messages.push((message_id, message.clone(), extra_delay));
                 }
-                released_messages.push(message);
-                released_messages
+                messages.push((message_id, message, extra_delay));
+                messages
                     .into_iter()
-                    .map(|m| DeliverMessage {
-                        message: m,
-                        delay: self.calculate_delay(rand),
+                    .filter_map(|(id, m, extra_delay)| {
+                        self.admit(m, id, extra_delay, now, rand, deliveries)
                     })
                     .collect()
             }
             LinkState::TempHold {
                 queued_messages, ..
             } => {
-                queued_messages.push(message);
+                let to = message.destination();
+                let is_full = self.config.link_queue_capacity.is_some_and(|capacity| {
+                    queued_messages
+                        .iter()
+                        .filter(|(_, m)| m.destination() == to)
+                        .count()
+                        >= capacity
+                });
+                if is_full {
+                    match self.config.queue_overflow_policy {
+                        OverflowPolicy::DropTail => {
+                            deliveries.record_drop(message, DropReason::Overflow);
+                            return vec![];
+                        }
+                        OverflowPolicy::DropHead => {
+                            let oldest = queued_messages
+                                .iter()
+                                .position(|(_, m)| m.destination() == to);
+                            if let Some((_, evicted)) =
+                                oldest.and_then(|i| queued_messages.remove(i))
+                            {
+                                deliveries.record_drop(evicted, DropReason::Overflow);
+                            }
+                        }
+                    }
+                }
+                queued_messages.push_back((message_id, message));
                 vec![]
             }
-            _ => vec![], // Messages are dropped in other states
+            LinkState::TempFailure { .. } => {
+                deliveries.record_drop(message, DropReason::LinkFailure);
+                vec![]
+            }
+        }
+    }
+
+    // Puts a message in flight if the in-flight queue of its direction has
+    // room for it (possibly by evicting the oldest message in flight).
+    fn admit(
+        &mut self,
+        message: M,
+        message_id: usize,
+        extra_delay: ExtraDelay,
+        now: Instant,
+        rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
+    ) -> Option<DeliverMessage<M>> {
+        let (from, to) = (message.source(), message.destination());
+        let in_flight = self.in_flight.entry(to).or_default();
+        let is_full = self
+            .config
+            .link_queue_capacity
+            .is_some_and(|capacity| in_flight.len() >= capacity);
+        if is_full {
+            match self.config.queue_overflow_policy {
+                OverflowPolicy::DropTail => {
+                    debug!(
+                        time = ?now.duration_since(self.simulation_start),
+                        from = ?from,
+                        to = ?to,
+                        "Link queue full, dropping new message"
+                    );
+                    deliveries.record_drop(message, DropReason::Overflow);
+                    return None;
+                }
+                OverflowPolicy::DropHead => {
+                    if let Some(evicted) = in_flight.pop_front() {
+                        debug!(
+                            time = ?now.duration_since(self.simulation_start),
+                            from = ?from,
+                            to = ?to,
+                            delivery_id = evicted.delivery_id,
+                            "Link queue full, dropping oldest message in flight"
+                        );
+                        deliveries.cancel(evicted.delivery_id);
+                    }
+                }
+            }
         }
+
+        let delay = extra_delay.apply(now, self.calculate_delay(rand));
+        let id = deliveries.next_id();
+        self.in_flight.entry(to).or_default().push_back(InFlight {
+            delivery_time: now + delay,
+            delivery_id: id,
+        });
+        Some(DeliverMessage {
+            message,
+            message_id,
+            delay,
+            id,
+        })
     }
 
-    fn check_state_transition(&mut self, now: Instant, rand: &mut dyn RngCore) -> Vec<M> {
-        let mut released_messages = vec![];
+    fn check_state_transition(
+        &mut self,
+        now: Instant,
+        rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
+    ) -> VecDeque<(usize, M)> {
+        let mut released_messages = VecDeque::new();
         let mut new_state = None;
-        match &self.state {
+        match &mut self.state {
             LinkState::Up {
                 expected_failure: Some(ef),
                 ..
-            } => {
-                if now >= *ef {
-                    if rand.gen_bool(self.config.hold_probability) {
-                        new_state = Some(LinkState::TempHold {
-                            expected_recovery: sample_failure_time(
-                                now,
-                                self.config.mean_link_recovery_time,
-                                rand,
-                            ),
-                            queued_messages: Vec::new(),
-                        });
-                    } else {
-                        new_state = Some(LinkState::TempFailure {
-                            expected_recovery: sample_failure_time(
-                                now,
-                                self.config.mean_link_recovery_time,
-                                rand,
-                            ),
-                        });
-                    }
+            } if now >= *ef => {
+                if rand.gen_bool(self.config.hold_probability) {
+                    new_state = Some(LinkState::TempHold {
+                        expected_recovery: sample_failure_time(
+                            now,
+                            self.config.mean_link_recovery_time,
+                            rand,
+                        ),
+                        queued_messages: VecDeque::new(),
+                    });
+                } else {
+                    new_state = Some(LinkState::TempFailure {
+                        expected_recovery: sample_failure_time(
+                            now,
+                            self.config.mean_link_recovery_time,
+                            rand,
+                        ),
+                    });
                 }
             }
-            LinkState::TempFailure {
-                expected_recovery, ..
+            LinkState::TempFailure { expected_recovery } if now >= *expected_recovery => {
+                new_state = Some(Self::gen_up_state(now, rand, &self.config));
             }
-            | LinkState::TempHold {
-                expected_recovery, ..
-            } => {
-                if now >= *expected_recovery {
-                    if let LinkState::TempHold {
-                        queued_messages, ..
-                    } = &mut self.state
-                    {
-                        std::mem::swap(&mut released_messages, queued_messages);
-                    }
-                    new_state = Some(Self::gen_up_state(now, rand, &self.config));
-                }
+            LinkState::TempHold {
+                expected_recovery,
+                queued_messages,
+            } if now >= *expected_recovery => {
+                std::mem::swap(&mut released_messages, queued_messages);
+                new_state = Some(Self::gen_up_state(now, rand, &self.config));
             }
             _ => {}
         };
 
         if let Some(new_state) = new_state {
-            let description = match &self.state {
-                LinkState::Up { .. } => "is up again",
-                LinkState::TempFailure { .. } => "failed",
-                LinkState::TempHold { .. } => "failed (and messages are held)",
+            let (description, status) = match &new_state {
+                LinkState::Up { .. } => ("is up again", LinkStatus::Up),
+                LinkState::TempFailure { .. } => ("failed", LinkStatus::Failed),
+                LinkState::TempHold { .. } => {
+                    ("failed (and messages are held)", LinkStatus::Holding)
+                }
             };
             info!(
                 time = ?now.duration_since(self.simulation_start),
@@ -161,6 +348,11 @@ impl<M: Clone + std::fmt::Debug> Link<M> {
                 "Link {}",
                 description,
             );
+            deliveries.events.push(TraceEvent::LinkChange {
+                from: self.from,
+                to: self.to,
+                status,
+            });
 
             self.state = new_state;
         }
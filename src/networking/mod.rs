@@ -2,6 +2,9 @@ mod config;
 mod link;
 mod network;
 mod partition;
+mod rules;
 
-pub use config::NetworkConfig;
+pub use config::{LatencyDistribution, NetworkConfig, OverflowPolicy};
+pub use link::LinkState;
 pub use network::*;
+pub use rules::{FaultScript, Rule, RuleAction, RulePredicate};
@@ -1,28 +1,94 @@
 use rand::RngCore;
 
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
 use crate::node::NodeId;
+use crate::trace::{DropReason, TraceEvent};
 use crate::ProtocolMessage;
 
-use super::link::Link;
+use super::link::{ExtraDelay, Link, LinkState};
 use super::partition::NetworkPartition;
-use super::NetworkConfig;
+use super::{NetworkConfig, Rule, RuleAction};
 
 #[derive(Debug, Clone)]
 pub struct DeliverMessage<M> {
     pub message: M,
+    /// Id the message was sent with. Messages a link held back keep it.
+    pub message_id: usize,
     pub delay: Duration,
+    /// Unique id of this delivery. Used to cancel the delivery if the message
+    /// is evicted from a full link queue while in flight.
+    pub id: u64,
 }
 
+/// Counts of messages dropped by the network, by cause.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct NetworkMetrics {
+    /// Messages dropped because the link was down.
+    pub link_failure_drops: usize,
+    /// Messages dropped because sender and receiver were partitioned.
+    pub partition_drops: usize,
+    /// Messages dropped because a link queue was full.
+    pub overflow_drops: usize,
+    /// Messages dropped by a rule.
+    pub rule_drops: usize,
+}
+
+/// Bookkeeping shared by all links: delivery ids, deliveries cancelled due to
+/// queue overflow, drop metrics and events not yet collected by the simulator.
+#[derive(Debug, Clone)]
+pub(super) struct DeliveryTracker<M> {
+    next_id: u64,
+    cancelled: HashSet<u64>,
+    metrics: NetworkMetrics,
+    pub(super) events: Vec<TraceEvent<M>>,
+}
+
+impl<M> DeliveryTracker<M> {
+    fn new() -> Self {
+        DeliveryTracker {
+            next_id: 0,
+            cancelled: HashSet::new(),
+            metrics: NetworkMetrics::default(),
+            events: vec![],
+        }
+    }
+
+    pub(super) fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Cancels the delivery of a message evicted from a full link queue. The
+    /// drop event is recorded by the simulator once the delivery is due.
+    pub(super) fn cancel(&mut self, id: u64) {
+        self.metrics.overflow_drops += 1;
+        self.cancelled.insert(id);
+    }
+
+    pub(super) fn record_drop(&mut self, message: M, reason: DropReason) {
+        match reason {
+            DropReason::LinkFailure => self.metrics.link_failure_drops += 1,
+            DropReason::Partition => self.metrics.partition_drops += 1,
+            DropReason::Overflow => self.metrics.overflow_drops += 1,
+            DropReason::Rule => self.metrics.rule_drops += 1,
+            DropReason::NodeDown => {}
+        }
+        self.events.push(TraceEvent::Drop { message, reason });
+    }
+}
+
+#[derive(Clone)]
 pub struct Network<M> {
     links: HashMap<(NodeId, NodeId), Link<M>>,
     partitioning: NetworkPartition,
     config: Rc<NetworkConfig>,
     simulation_start: Instant,
+    deliveries: DeliveryTracker<M>,
+    rules: Vec<Rule<M>>,
 }
 
 impl<M> Network<M>
@@ -41,19 +107,92 @@ where
             config: shared_config.clone(),
             partitioning: NetworkPartition::new(simulation_start, nodes, shared_config, rand),
             simulation_start,
+            deliveries: DeliveryTracker::new(),
+            rules: vec![],
         }
     }
 
     pub fn send(
         &mut self,
         message: M,
+        message_id: usize,
+        now: Instant,
+        rand: &mut dyn RngCore,
+    ) -> Vec<DeliverMessage<M>> {
+        let from = message.source();
+        let to = message.destination();
+        let time = now.duration_since(self.simulation_start);
+        let action = self
+            .rules
+            .iter_mut()
+            .find_map(|rule| rule.apply(from, to, &message, time));
+
+        let no_delay = ExtraDelay::default();
+        match action {
+            None => self.transmit(message, message_id, no_delay, now, rand),
+            Some(RuleAction::Drop) => {
+                self.deliveries.record_drop(message, DropReason::Rule);
+                vec![]
+            }
+            Some(RuleAction::Delay(delay)) => {
+                let extra_delay = ExtraDelay {
+                    delay,
+                    not_before: None,
+                };
+                self.transmit(message, message_id, extra_delay, now, rand)
+            }
+            Some(RuleAction::Duplicate(copies)) => {
+                let mut deliveries = vec![];
+                for _ in 0..copies {
+                    self.deliveries.events.push(TraceEvent::Duplicate {
+                        message: message.clone(),
+                    });
+                    deliveries.extend(self.transmit(
+                        message.clone(),
+                        message_id,
+                        no_delay,
+                        now,
+                        rand,
+                    ));
+                }
+                deliveries.extend(self.transmit(message, message_id, no_delay, now, rand));
+                deliveries
+            }
+            Some(RuleAction::HoldUntil(until)) => {
+                let extra_delay = ExtraDelay {
+                    delay: Duration::ZERO,
+                    not_before: Some(self.simulation_start + until),
+                };
+                self.transmit(message, message_id, extra_delay, now, rand)
+            }
+            Some(RuleAction::Redirect(redirect)) => {
+                self.transmit(redirect(&message), message_id, no_delay, now, rand)
+            }
+        }
+    }
+
+    /// Adds a rule that applies to all messages sent from now on.
+    pub fn add_rule(&mut self, rule: Rule<M>) {
+        self.rules.push(rule);
+    }
+
+    // Sends a message according to the partitions and the link model.
+    fn transmit(
+        &mut self,
+        message: M,
+        message_id: usize,
+        extra_delay: ExtraDelay,
         now: Instant,
         rand: &mut dyn RngCore,
     ) -> Vec<DeliverMessage<M>> {
         let from = message.source();
         let to = message.destination();
 
-        if self.partitioning.is_partitioned(now, &from, &to, rand) {
+        if self
+            .partitioning
+            .is_partitioned(now, &from, &to, rand, &mut self.deliveries)
+        {
+            self.deliveries.record_drop(message, DropReason::Partition);
             return vec![];
         }
 
@@ -75,6 +214,97 @@ where
                     rand,
                 )
             })
-            .send(message, now, rand)
+            .send(
+                message,
+                message_id,
+                extra_delay,
+                now,
+                rand,
+                &mut self.deliveries,
+            )
+    }
+
+    /// Adds a node to the set of nodes considered for partitions.
+    pub fn add_node(&mut self, node: NodeId) {
+        self.partitioning.add_node(node);
+    }
+
+    /// Removes a node from the set of nodes considered for partitions.
+    pub fn remove_node(&mut self, node: NodeId) {
+        self.partitioning.remove_node(node);
+    }
+
+    /// Returns whether the delivery with the given id was cancelled because it
+    /// was evicted from a full link queue. Each cancellation is only reported
+    /// once.
+    pub fn is_cancelled(&mut self, delivery_id: u64) -> bool {
+        self.deliveries.cancelled.remove(&delivery_id)
+    }
+
+    /// Disables link failures, partitions and duplicates from now on. Failed
+    /// links come back up and put the messages they held back in flight.
+    pub fn heal(&mut self, now: Instant, rand: &mut dyn RngCore) -> Vec<DeliverMessage<M>> {
+        self.config = Rc::new(self.config.without_faults());
+        self.partitioning
+            .heal(now, self.config.clone(), &mut self.deliveries);
+
+        let mut endpoints = self.links.keys().copied().collect::<Vec<_>>();
+        endpoints.sort();
+        let mut released_messages = vec![];
+        for endpoints in endpoints {
+            let link = self.links.get_mut(&endpoints).unwrap();
+            released_messages.extend(link.heal(
+                now,
+                self.config.clone(),
+                rand,
+                &mut self.deliveries,
+            ));
+        }
+        released_messages
+    }
+
+    /// Returns whether a failed link holds back messages.
+    pub(crate) fn has_held_messages(&self) -> bool {
+        self.links.values().any(Link::has_held_messages)
+    }
+
+    /// Returns whether the delivery with the given id was cancelled, without
+    /// consuming the cancellation.
+    pub(crate) fn is_delivery_cancelled(&self, delivery_id: u64) -> bool {
+        self.deliveries.cancelled.contains(&delivery_id)
+    }
+
+    /// Returns the state of the link between the nodes, or nothing if no
+    /// message was sent on it yet.
+    pub(crate) fn link_state(&self, a: NodeId, b: NodeId) -> Option<&LinkState<M>> {
+        self.links
+            .get(&(cmp::min(a, b), cmp::max(a, b)))
+            .map(Link::state)
+    }
+
+    /// Returns the states of all links that carried messages, ordered by their
+    /// endpoints.
+    pub(crate) fn links(&self) -> Vec<(NodeId, NodeId, &LinkState<M>)> {
+        let mut links = self
+            .links
+            .iter()
+            .map(|((a, b), link)| (*a, *b, link.state()))
+            .collect::<Vec<_>>();
+        links.sort_by_key(|(a, b, _)| (*a, *b));
+        links
+    }
+
+    pub(crate) fn partitions(&self) -> Vec<Vec<NodeId>> {
+        self.partitioning.partitions()
+    }
+
+    pub fn metrics(&self) -> &NetworkMetrics {
+        &self.deliveries.metrics
+    }
+
+    /// Returns the drops, link changes and partition changes since the last
+    /// call.
+    pub(crate) fn take_events(&mut self) -> Vec<TraceEvent<M>> {
+        std::mem::take(&mut self.deliveries.events)
     }
 }
@@ -2,16 +2,19 @@ use rand::seq::SliceRandom;
 use rand::{Rng, RngCore};
 use tracing::info;
 
+use derive_more::derive::IsVariant;
 use std::collections::HashSet;
 use std::rc::Rc;
 use std::time::Instant;
 
 use crate::node::NodeId;
+use crate::trace::TraceEvent;
 use crate::util::sample_failure_time;
 
+use super::network::DeliveryTracker;
 use super::NetworkConfig;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, IsVariant)]
 enum PartitionState {
     Normal {
         expected_partition: Option<Instant>,
@@ -33,6 +36,7 @@ impl PartitionState {
     }
 }
 
+#[derive(Clone)]
 pub struct NetworkPartition {
     partition_state: PartitionState,
     nodes: Vec<NodeId>,
@@ -59,33 +63,34 @@ impl NetworkPartition {
         }
     }
 
-    pub fn check_partition_state_transition(&mut self, now: Instant, rand: &mut dyn RngCore) {
+    pub(super) fn check_partition_state_transition<M>(
+        &mut self,
+        now: Instant,
+        rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
+    ) {
         let mut new_state = None;
         match &self.partition_state {
             PartitionState::Normal {
                 expected_partition: Some(ep),
-            } => {
-                if now >= *ep {
-                    new_state = Some(PartitionState::Partition {
-                        partioned_nodes: sample_random_subset(&self.nodes, 1, rand),
-                        expected_recovery: sample_failure_time(
-                            now,
-                            self.config.mean_partition_recovery_time,
-                            rand,
-                        ),
-                    });
-                }
+            } if now >= *ep => {
+                new_state = Some(PartitionState::Partition {
+                    partioned_nodes: sample_random_subset(&self.nodes, 1, rand),
+                    expected_recovery: sample_failure_time(
+                        now,
+                        self.config.mean_partition_recovery_time,
+                        rand,
+                    ),
+                });
             }
             PartitionState::Partition {
                 expected_recovery, ..
-            } => {
-                if now >= *expected_recovery {
-                    let expected_partition = self
-                        .config
-                        .mean_time_between_partitions
-                        .map(|mtbp| sample_failure_time(now, mtbp, rand));
-                    new_state = Some(PartitionState::Normal { expected_partition });
-                }
+            } if now >= *expected_recovery => {
+                let expected_partition = self
+                    .config
+                    .mean_time_between_partitions
+                    .map(|mtbp| sample_failure_time(now, mtbp, rand));
+                new_state = Some(PartitionState::Normal { expected_partition });
             }
             _ => {}
         };
@@ -97,6 +102,7 @@ impl NetworkPartition {
                         time = ?now.duration_since(self.simulation_start),
                         "Network partition ended"
                     );
+                    deliveries.events.push(TraceEvent::PartitionEnd);
                 }
                 PartitionState::Partition {
                     partioned_nodes,
@@ -107,6 +113,11 @@ impl NetworkPartition {
                         partitioned_nodes = ?partioned_nodes,
                         "Network partition started"
                     );
+                    let mut partitioned = partioned_nodes.iter().copied().collect::<Vec<_>>();
+                    partitioned.sort();
+                    deliveries
+                        .events
+                        .push(TraceEvent::PartitionStart(partitioned));
                 }
             }
 
@@ -114,14 +125,61 @@ impl NetworkPartition {
         }
     }
 
-    pub fn is_partitioned(
+    /// Returns both sides of the current partition (each sorted), or nothing
+    /// if the network is not partitioned.
+    pub(super) fn partitions(&self) -> Vec<Vec<NodeId>> {
+        let PartitionState::Partition {
+            partioned_nodes, ..
+        } = &self.partition_state
+        else {
+            return vec![];
+        };
+        let (mut partitioned, mut rest): (Vec<NodeId>, Vec<NodeId>) = self
+            .nodes
+            .iter()
+            .partition(|node| partioned_nodes.contains(node));
+        partitioned.sort();
+        rest.sort();
+        vec![partitioned, rest]
+    }
+
+    /// Ends the current partition and prevents new ones.
+    pub(super) fn heal<M>(
+        &mut self,
+        now: Instant,
+        config: Rc<NetworkConfig>,
+        deliveries: &mut DeliveryTracker<M>,
+    ) {
+        self.config = config;
+        if self.partition_state.is_partition() {
+            info!(
+                time = ?now.duration_since(self.simulation_start),
+                "Network partition healed"
+            );
+            deliveries.events.push(TraceEvent::PartitionEnd);
+        }
+        self.partition_state = PartitionState::Normal {
+            expected_partition: None,
+        };
+    }
+
+    pub fn add_node(&mut self, node: NodeId) {
+        self.nodes.push(node);
+    }
+
+    pub fn remove_node(&mut self, node: NodeId) {
+        self.nodes.retain(|n| *n != node);
+    }
+
+    pub(super) fn is_partitioned<M>(
         &mut self,
         now: Instant,
         from: &NodeId,
         to: &NodeId,
         rand: &mut dyn RngCore,
+        deliveries: &mut DeliveryTracker<M>,
     ) -> bool {
-        self.check_partition_state_transition(now, rand);
+        self.check_partition_state_transition(now, rand, deliveries);
         self.partition_state.is_partitioned(from, to)
     }
 }
@@ -0,0 +1,117 @@
+use std::{rc::Rc, time::Duration};
+
+use crate::node::NodeId;
+
+/// Decides whether a rule applies to a message, given its sender, receiver and
+/// the simulated time it is sent at.
+pub type RulePredicate<M> = dyn Fn(NodeId, NodeId, &M, Duration) -> bool;
+
+/// What happens to a message that matches a [`Rule`].
+#[derive(Clone)]
+pub enum RuleAction<M> {
+    /// The message is lost.
+    Drop,
+    /// The message is delivered the given time later than the link decides.
+    Delay(Duration),
+    /// The given number of additional copies of the message is sent.
+    Duplicate(usize),
+    /// The message is not delivered before the given simulated time.
+    HoldUntil(Duration),
+    /// The message is replaced by the returned one, e.g., a copy with a
+    /// different destination.
+    Redirect(Rc<dyn Fn(&M) -> M>),
+}
+
+/// Targets specific messages with an action before the link model (partitions,
+/// link failures, latency) applies. For example,
+/// `Rule::new(is_commit_to_node_2, RuleAction::Drop).skip(2).limit(1)` drops
+/// only the third matching message. If several rules match a message, only the
+/// first one added applies.
+#[derive(Clone)]
+pub struct Rule<M> {
+    predicate: Rc<RulePredicate<M>>,
+    action: RuleAction<M>,
+    skip: usize,
+    limit: Option<usize>,
+    matched: usize,
+}
+
+impl<M> Rule<M> {
+    pub fn new(
+        predicate: impl Fn(NodeId, NodeId, &M, Duration) -> bool + 'static,
+        action: RuleAction<M>,
+    ) -> Self {
+        Rule {
+            predicate: Rc::new(predicate),
+            action,
+            skip: 0,
+            limit: None,
+            matched: 0,
+        }
+    }
+
+    /// Lets the first `count` matching messages pass unchanged.
+    pub fn skip(mut self, count: usize) -> Self {
+        self.skip = count;
+        self
+    }
+
+    /// Applies the action to at most `count` messages.
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.limit
+            .is_some_and(|limit| self.matched >= self.skip + limit)
+    }
+
+    /// Returns the action if the rule applies to the message.
+    pub(super) fn apply(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        message: &M,
+        time: Duration,
+    ) -> Option<RuleAction<M>>
+    where
+        M: Clone,
+    {
+        if self.is_exhausted() || !(self.predicate)(from, to, message, time) {
+            return None;
+        }
+        self.matched += 1;
+        (self.matched > self.skip).then(|| self.action.clone())
+    }
+}
+
+/// Rules that take effect at given simulated times, e.g., to drop all
+/// messages to a node from 5s on: `FaultScript::new().rule_at(5s, rule)`.
+#[derive(Clone)]
+pub struct FaultScript<M> {
+    rules: Vec<(Duration, Rule<M>)>,
+}
+
+impl<M> FaultScript<M> {
+    pub fn new() -> Self {
+        FaultScript { rules: vec![] }
+    }
+
+    /// Adds the rule once the simulation reaches the given time, i.e., it
+    /// applies to messages sent at or after that time.
+    pub fn rule_at(mut self, time: Duration, rule: Rule<M>) -> Self {
+        self.rules.push((time, rule));
+        self
+    }
+
+    pub(crate) fn into_rules(self) -> Vec<(Duration, Rule<M>)> {
+        self.rules
+    }
+}
+
+impl<M> Default for FaultScript<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,12 +1,12 @@
 use rand::prelude::*;
 use rand_chacha::ChaCha8Rng;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{fmt, fmt::Debug};
 use tracing::info;
 
 use derive_more::derive::IsVariant;
 
-use crate::{util::sample_failure_time, DeterministicNode, FailureConfiguration};
+use crate::{util::sample_failure_time, DeterministicNode, FailureConfiguration, MembershipChange};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum NodeId {
@@ -23,19 +23,60 @@ impl fmt::Display for NodeId {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Node<N: DeterministicNode> {
     node: N,
     state: NodeState,
     failure_config: FailureConfiguration,
     replica_count: usize,
     start_time: Instant,
+    busy_until: Instant,
+    tick_deferred: bool,
 }
 
-#[derive(Debug, IsVariant)]
-enum NodeState {
+#[derive(Debug, Clone, IsVariant)]
+pub(crate) enum NodeState {
     Normal { failure_time: Option<Instant> },
     Failed { recovery_time: Instant },
+    // The node was permanently removed from the simulation.
+    Decommissioned,
+}
+
+impl NodeState {
+    pub(crate) fn new(
+        now: Instant,
+        failure_config: &FailureConfiguration,
+        rand: &mut dyn RngCore,
+    ) -> Self {
+        let failure_time =
+            failure_config
+                .mean_time_between_failures
+                .map(|mean_time_between_failures| {
+                    sample_failure_time(now, mean_time_between_failures, rand)
+                });
+        NodeState::Normal { failure_time }
+    }
+
+    /// Returns the new state if the node crashes or restarts at the given time.
+    pub(crate) fn transition(
+        &self,
+        now: Instant,
+        can_fail: bool,
+        failure_config: &FailureConfiguration,
+        rand: &mut dyn RngCore,
+    ) -> Option<NodeState> {
+        match self {
+            NodeState::Normal {
+                failure_time: Some(failure_time),
+            } if now >= *failure_time && can_fail => Some(NodeState::Failed {
+                recovery_time: sample_failure_time(now, failure_config.mean_time_to_recover, rand),
+            }),
+            NodeState::Failed { recovery_time } if now >= *recovery_time => {
+                Some(NodeState::new(now, failure_config, rand))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl<N: DeterministicNode> Node<N> {
@@ -44,21 +85,17 @@ impl<N: DeterministicNode> Node<N> {
         failure_config: FailureConfiguration,
         rng: &mut ChaCha8Rng,
         start_time: Instant,
+        now: Instant,
         replica_count: usize,
     ) -> Self {
-        let failure_time =
-            failure_config
-                .mean_time_between_failures
-                .map(|mean_time_between_failures| {
-                    sample_failure_time(start_time, mean_time_between_failures, rng)
-                });
-
         Node {
             node,
-            state: NodeState::Normal { failure_time },
+            state: NodeState::new(now, &failure_config, rng),
             failure_config,
             replica_count,
             start_time,
+            busy_until: now,
+            tick_deferred: false,
         }
     }
 
@@ -71,37 +108,110 @@ impl<N: DeterministicNode> Node<N> {
     }
 
     pub fn is_up(&self) -> bool {
-        !(self.state.is_failed() || self.node.is_recovering())
+        self.state.is_normal() && !self.node.is_recovering()
     }
 
-    fn has_failed(&mut self, now: Instant, can_fail: bool, rand: &mut dyn RngCore) -> bool {
-        let mut new_state = None;
-        match &self.state {
-            NodeState::Normal { failure_time } => {
-                if let Some(failure_time) = failure_time {
-                    if now >= *failure_time && can_fail {
-                        new_state = Some(NodeState::Failed {
-                            recovery_time: sample_failure_time(
-                                now,
-                                self.failure_config.mean_time_between_failures.unwrap(),
-                                rand,
-                            ),
-                        });
-                    }
-                }
-            }
-            NodeState::Failed { recovery_time } => {
-                if now >= *recovery_time {
-                    new_state = Some(NodeState::Normal {
-                        failure_time: self.failure_config.mean_time_between_failures.map(
-                            |mean_time_between_failures| {
-                                sample_failure_time(now, mean_time_between_failures, rand)
-                            },
-                        ),
-                    });
-                }
+    /// Returns whether the node was removed from the simulation.
+    pub fn is_decommissioned(&self) -> bool {
+        self.state.is_decommissioned()
+    }
+
+    pub(crate) fn decommission(&mut self, now: Instant) {
+        info!(
+            time = ?now.duration_since(self.start_time),
+            node = ?self.id(),
+            "Node decommissioned"
+        );
+        self.state = NodeState::Decommissioned;
+    }
+
+    pub(crate) fn is_crashed(&self) -> bool {
+        self.state.is_failed()
+    }
+
+    /// Crashes the node until it is restarted explicitly.
+    pub(crate) fn crash(&mut self, now: Instant) {
+        self.state = NodeState::Failed { recovery_time: now };
+    }
+
+    /// Restarts a crashed node. The node will not fail again by itself.
+    pub(crate) fn restart(&mut self, now: Instant, nonce: u64) {
+        self.state = NodeState::Normal { failure_time: None };
+        self.node.recover(now, nonce, self.replica_count);
+    }
+
+    /// Restarts the node if it crashed. The node will not fail again.
+    pub(crate) fn heal(&mut self, now: Instant, rand: &mut dyn RngCore) {
+        self.failure_config.mean_time_between_failures = None;
+        match self.state {
+            NodeState::Normal { .. } => self.state = NodeState::Normal { failure_time: None },
+            NodeState::Failed { .. } => {
+                info!(
+                    time = ?now.duration_since(self.start_time),
+                    node = ?self.id(),
+                    "Node restarted"
+                );
+                self.state = NodeState::Normal { failure_time: None };
+                let nonce = rand.next_u64();
+                self.node.recover(now, nonce, self.replica_count);
             }
-        };
+            NodeState::Decommissioned => {}
+        }
+    }
+
+    pub(crate) fn set_replica_count(&mut self, replica_count: usize) {
+        self.replica_count = replica_count;
+    }
+
+    pub(crate) fn take_membership_changes(&mut self) -> Vec<MembershipChange> {
+        if !self.state.is_normal() {
+            return vec![];
+        }
+        self.node.membership_changes()
+    }
+
+    /// Returns whether the node is still processing an earlier event.
+    pub fn is_busy(&self, now: Instant) -> bool {
+        now < self.busy_until
+    }
+
+    pub(crate) fn busy_until(&self) -> Instant {
+        self.busy_until
+    }
+
+    /// Marks the node as busy for the given processing time if it handled the
+    /// last event (i.e., it has not failed). Returns when the node is done.
+    pub(crate) fn occupy(&mut self, now: Instant, processing_time: Duration) -> Instant {
+        if self.state.is_normal() {
+            self.busy_until = now + processing_time;
+        }
+        self.busy_until
+    }
+
+    /// Records that a tick was postponed until the node is no longer busy.
+    /// Returns false if a tick is already pending, in which case the ticks are
+    /// coalesced.
+    pub(crate) fn defer_tick(&mut self) -> bool {
+        !std::mem::replace(&mut self.tick_deferred, true)
+    }
+
+    /// Returns whether a postponed tick is still pending and clears it.
+    pub(crate) fn take_deferred_tick(&mut self) -> bool {
+        std::mem::replace(&mut self.tick_deferred, false)
+    }
+
+    pub(crate) fn message_processing_time(&self, msg: &N::Message) -> Option<Duration> {
+        self.node.message_processing_time(msg)
+    }
+
+    pub(crate) fn tick_processing_time(&self) -> Option<Duration> {
+        self.node.tick_processing_time()
+    }
+
+    fn has_failed(&mut self, now: Instant, can_fail: bool, rand: &mut dyn RngCore) -> bool {
+        let new_state = self
+            .state
+            .transition(now, can_fail, &self.failure_config, rand);
 
         if let Some(new_state) = new_state {
             if new_state.is_normal() {
@@ -123,10 +233,11 @@ impl<N: DeterministicNode> Node<N> {
             self.state = new_state;
         }
 
-        self.state.is_failed()
+        !self.state.is_normal()
     }
 
     pub fn tick(&mut self, now: Instant, rand: &mut dyn RngCore) -> Vec<N::Message> {
+        self.tick_deferred = false;
         if self.has_failed(now, false, rand) {
             return vec![];
         }
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::{
+    node::NodeId,
+    trace::{DropReason, LinkStatus, TraceEvent},
+};
+
+/// Typed callbacks for everything that happens during a simulation, e.g., to
+/// compute custom metrics or coverage. Observers are registered with
+/// [`crate::Simulator::add_observer`]. All callbacks receive the simulated
+/// time and do nothing by default.
+///
+/// Link and partition changes are only noticed when a message is sent, so they
+/// are reported at the time of the first send that observes them.
+pub trait SimulationObserver<M> {
+    /// A message was handed to the network. Duplicates share the id of the
+    /// original message.
+    fn on_send(&mut self, _time: Duration, _message_id: usize, _message: &M) {}
+
+    /// A message was processed by its receiver.
+    fn on_deliver(&mut self, _time: Duration, _message_id: usize, _message: &M) {}
+
+    /// A message was lost.
+    fn on_drop(&mut self, _time: Duration, _message: &M, _reason: DropReason) {}
+
+    /// The network duplicated a message.
+    fn on_duplicate(&mut self, _time: Duration, _message: &M) {}
+
+    fn on_node_crash(&mut self, _time: Duration, _node: NodeId) {}
+
+    fn on_node_recover(&mut self, _time: Duration, _node: NodeId) {}
+
+    fn on_link_state_change(
+        &mut self,
+        _time: Duration,
+        _from: NodeId,
+        _to: NodeId,
+        _status: LinkStatus,
+    ) {
+    }
+
+    /// The given nodes were partitioned from the rest.
+    fn on_partition_start(&mut self, _time: Duration, _partitioned: &[NodeId]) {}
+
+    fn on_partition_end(&mut self, _time: Duration) {}
+
+    /// All nodes and clients were ticked.
+    fn on_tick(&mut self, _time: Duration) {}
+
+    /// The invariants were checked (and held).
+    fn on_invariant_check(&mut self, _time: Duration) {}
+}
+
+impl<M> TraceEvent<M> {
+    /// Calls the callback of the observer that corresponds to this event.
+    pub fn notify(&self, time: Duration, observer: &mut dyn SimulationObserver<M>) {
+        match self {
+            TraceEvent::Send {
+                message_id,
+                message,
+            } => observer.on_send(time, *message_id, message),
+            TraceEvent::Deliver {
+                message_id,
+                message,
+            } => observer.on_deliver(time, *message_id, message),
+            TraceEvent::Duplicate { message } => observer.on_duplicate(time, message),
+            TraceEvent::Drop { message, reason } => observer.on_drop(time, message, *reason),
+            TraceEvent::NodeCrash(node) => observer.on_node_crash(time, *node),
+            TraceEvent::NodeRecover(node) => observer.on_node_recover(time, *node),
+            TraceEvent::LinkChange { from, to, status } => {
+                observer.on_link_state_change(time, *from, *to, *status)
+            }
+            TraceEvent::PartitionStart(nodes) => observer.on_partition_start(time, nodes),
+            TraceEvent::PartitionEnd => observer.on_partition_end(time),
+            TraceEvent::InvariantCheck => observer.on_invariant_check(time),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use crate::{Configuration, FailureConfiguration, LatencyDistribution, NetworkConfig};
+
+// Builds a network configuration from the parameters that differ between the
+// presets. Queues are unbounded in all presets.
+fn network(
+    latency: (u64, u64),
+    latency_distribution: LatencyDistribution,
+    duplicate_probability: f64,
+    link_failures: Option<(u64, u64, f64)>,
+    partitions: Option<(u64, u64)>,
+) -> NetworkConfig {
+    let (min_latency, max_latency) = latency;
+    let (mean_time_between_link_failures, mean_link_recovery_time, hold_probability) =
+        match link_failures {
+            Some((between, recovery, hold)) => (Some(ms(between)), ms(recovery), hold),
+            None => (None, ms(0), 0.0),
+        };
+    let (mean_time_between_partitions, mean_partition_recovery_time) = match partitions {
+        Some((between, recovery)) => (Some(ms(between)), ms(recovery)),
+        None => (None, ms(0)),
+    };
+    NetworkConfig {
+        min_message_latency: ms(min_latency),
+        max_message_latency: ms(max_latency),
+        latency_distribution,
+        duplicate_probability,
+        hold_probability,
+        mean_time_between_link_failures,
+        mean_link_recovery_time,
+        mean_time_between_partitions,
+        mean_partition_recovery_time,
+        ..NetworkConfig::default()
+    }
+}
+
+fn failures(node_failures: Option<(u64, u64)>) -> FailureConfiguration {
+    match node_failures {
+        Some((between, recovery)) => FailureConfiguration {
+            mean_time_between_failures: Some(ms(between)),
+            mean_time_to_recover: ms(recovery),
+        },
+        None => FailureConfiguration {
+            mean_time_between_failures: None,
+            mean_time_to_recover: ms(0),
+        },
+    }
+}
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Named fault profiles as a starting point for tests. All other fields have
+/// their default values, so e.g. the seed or the simulated time can be set
+/// with `Configuration { seed, ..Configuration::wan() }`.
+impl Configuration {
+    /// No faults at all.
+    ///
+    /// - latency: 1-10ms, exponential (rate 5)
+    /// - no duplicates, link failures, partitions or node failures
+    pub fn reliable() -> Self {
+        Configuration {
+            network_config: network(
+                (1, 10),
+                LatencyDistribution::Exponential { rate: 5.0 },
+                0.0,
+                None,
+                None,
+            ),
+            failure_config: failures(None),
+            ..Configuration::default()
+        }
+    }
+
+    /// A data center network with rare faults.
+    ///
+    /// - latency: 0-2ms, exponential (rate 5)
+    /// - duplicates: 0.1%
+    /// - link failures: every 60s on average, recovering after 100ms, 50% of
+    ///   them hold messages
+    /// - no partitions
+    /// - node failures: every 30s on average, recovering after 2s
+    pub fn lan() -> Self {
+        Configuration {
+            network_config: network(
+                (0, 2),
+                LatencyDistribution::Exponential { rate: 5.0 },
+                0.001,
+                Some((60_000, 100, 0.5)),
+                None,
+            ),
+            failure_config: failures(Some((30_000, 2_000))),
+            ..Configuration::default()
+        }
+    }
+
+    /// A geo-distributed network with high, varying latencies.
+    ///
+    /// - latency: 20-300ms, normal (mean 30%, standard deviation 15% of the
+    ///   range)
+    /// - duplicates: 1%
+    /// - link failures: every 10s on average, recovering after 500ms, 50% of
+    ///   them hold messages
+    /// - partitions: every 30s on average, healing after 2s
+    /// - node failures: every 30s on average, recovering after 3s
+    pub fn wan() -> Self {
+        Configuration {
+            network_config: network(
+                (20, 300),
+                LatencyDistribution::Normal {
+                    mean: 0.3,
+                    std_dev: 0.15,
+                },
+                0.01,
+                Some((10_000, 500, 0.5)),
+                Some((30_000, 2_000)),
+            ),
+            failure_config: failures(Some((30_000, 3_000))),
+            ..Configuration::default()
+        }
+    }
+
+    /// Unreliable links, but no partitions and few node failures.
+    ///
+    /// - latency: 0-200ms, exponential (rate 3)
+    /// - duplicates: 10%
+    /// - link failures: every 1s on average, recovering after 300ms, 30% of
+    ///   them hold messages
+    /// - no partitions
+    /// - node failures: every 10s on average, recovering after 1s
+    pub fn flaky() -> Self {
+        Configuration {
+            network_config: network(
+                (0, 200),
+                LatencyDistribution::Exponential { rate: 3.0 },
+                0.1,
+                Some((1_000, 300, 0.3)),
+                None,
+            ),
+            failure_config: failures(Some((10_000, 1_000))),
+            ..Configuration::default()
+        }
+    }
+
+    /// Frequent partitions on an otherwise reliable network.
+    ///
+    /// - latency: 0-100ms, exponential (rate 5)
+    /// - no duplicates or link failures
+    /// - partitions: every 1s on average, healing after 1s
+    /// - no node failures
+    pub fn partition_heavy() -> Self {
+        Configuration {
+            network_config: network(
+                (0, 100),
+                LatencyDistribution::Exponential { rate: 5.0 },
+                0.0,
+                None,
+                Some((1_000, 1_000)),
+            ),
+            failure_config: failures(None),
+            ..Configuration::default()
+        }
+    }
+
+    /// Frequent node crashes on an otherwise reliable network.
+    ///
+    /// - latency: 0-100ms, exponential (rate 5)
+    /// - no duplicates, link failures or partitions
+    /// - node failures: every 1s on average, recovering after 500ms
+    pub fn crash_heavy() -> Self {
+        Configuration {
+            network_config: network(
+                (0, 100),
+                LatencyDistribution::Exponential { rate: 5.0 },
+                0.0,
+                None,
+                None,
+            ),
+            failure_config: failures(Some((1_000, 500))),
+            ..Configuration::default()
+        }
+    }
+
+    /// All faults at high rates, including client failures.
+    ///
+    /// - latency: 0-500ms, exponential (rate 2)
+    /// - duplicates: 20%
+    /// - link failures: every 500ms on average, recovering after 300ms, 50% of
+    ///   them hold messages
+    /// - partitions: every 2s on average, healing after 1s
+    /// - node failures: every 1s on average, recovering after 1s
+    /// - client failures: every 3s on average, recovering after 1s
+    pub fn chaos() -> Self {
+        Configuration {
+            network_config: network(
+                (0, 500),
+                LatencyDistribution::Exponential { rate: 2.0 },
+                0.2,
+                Some((500, 300, 0.5)),
+                Some((2_000, 1_000)),
+            ),
+            failure_config: failures(Some((1_000, 1_000))),
+            client_failure_config: Some(failures(Some((3_000, 1_000)))),
+            ..Configuration::default()
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use rand::{Rng, RngCore};
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+use crate::{node::NodeId, Event, ProtocolMessage};
+
+/// Determines the order in which pending events are processed.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum SchedulingStrategy {
+    /// Events are processed in the order of their time, i.e., messages are
+    /// ordered by their sampled latency.
+    #[default]
+    TimeOrdered,
+    /// Probabilistic Concurrency Testing: messages are delivered in the order
+    /// of random priorities of their receivers, which gives probabilistic
+    /// guarantees for finding bugs of a given depth.
+    Pct(PctConfiguration),
+}
+
+/// Every node and client gets a random priority. Among the messages that are
+/// due before the next tick, the message to the receiver with the highest
+/// priority is delivered first. At `depth - 1` random steps, the priority of
+/// the receiver that was just scheduled is lowered below all others. A bug of
+/// depth `d` is found with a probability of at least `1 / (n * k^(d-1))` for
+/// `n` receivers and `k` steps.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct PctConfiguration {
+    /// Depth of the bugs to find.
+    pub depth: usize,
+    /// Expected number of message deliveries in a run (used to sample the
+    /// priority change points).
+    pub max_steps: usize,
+}
+
+impl Default for PctConfiguration {
+    fn default() -> Self {
+        PctConfiguration {
+            depth: 3,
+            max_steps: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) struct EventTime {
+    pub(crate) time: Instant,
+    // trick: to handle ties in the times, use an offset. We simply
+    // use the event count (number of events processed).
+    pub(crate) offset: usize,
+}
+
+#[derive(Debug, Clone)]
+struct PctScheduler {
+    depth: usize,
+    priorities: HashMap<NodeId, u64>,
+    // steps at which the priority of the scheduled receiver is lowered
+    change_points: Vec<usize>,
+    steps: usize,
+}
+
+impl PctScheduler {
+    fn new(config: &PctConfiguration, rand: &mut dyn RngCore) -> Self {
+        let change_points = (1..config.depth)
+            .map(|_| rand.gen_range(1..=config.max_steps.max(1)))
+            .collect();
+
+        PctScheduler {
+            depth: config.depth,
+            priorities: HashMap::new(),
+            change_points,
+            steps: 0,
+        }
+    }
+
+    fn assign_priority(&mut self, node: NodeId, rand: &mut dyn RngCore) {
+        // initial priorities are all higher than the ones after a change point
+        let depth = self.depth as u64;
+        self.priorities
+            .entry(node)
+            .or_insert_with(|| depth + rand.gen::<u32>() as u64);
+    }
+
+    fn scheduled(&mut self, node: NodeId) {
+        self.steps += 1;
+        for (i, change_point) in self.change_points.iter().enumerate() {
+            if *change_point == self.steps {
+                self.priorities.insert(node, (self.depth - 1 - i) as u64);
+            }
+        }
+    }
+}
+
+/// Pending events of the simulation.
+#[derive(Clone)]
+pub(crate) struct EventQueue<M: ProtocolMessage> {
+    events: BTreeMap<EventTime, Event<M>>,
+    total_event_count: usize,
+    // number of pending message and send events
+    message_count: usize,
+    pct: Option<PctScheduler>,
+}
+
+impl<M: ProtocolMessage> EventQueue<M> {
+    pub(crate) fn new(strategy: &SchedulingStrategy, rand: &mut dyn RngCore) -> Self {
+        let pct = match strategy {
+            SchedulingStrategy::TimeOrdered => None,
+            SchedulingStrategy::Pct(config) => Some(PctScheduler::new(config, rand)),
+        };
+
+        EventQueue {
+            events: BTreeMap::new(),
+            total_event_count: 0,
+            message_count: 0,
+            pct,
+        }
+    }
+
+    pub(crate) fn push(&mut self, time: Instant, event: Event<M>, rand: &mut dyn RngCore) {
+        if let (Some(pct), Event::Message(msg)) = (&mut self.pct, &event) {
+            pct.assign_priority(msg.message().destination(), rand);
+        }
+        if is_message(&event) {
+            self.message_count += 1;
+        }
+
+        self.events.insert(
+            EventTime {
+                time,
+                offset: self.total_event_count,
+            },
+            event,
+        );
+        self.total_event_count += 1;
+    }
+
+    /// Returns the event that is processed next.
+    pub(crate) fn peek(&self) -> Option<(&EventTime, &Event<M>)> {
+        let Some(pct) = &self.pct else {
+            return self.events.first_key_value();
+        };
+
+        // Messages due before the next tick can be reordered, ticks are
+        // processed in time order.
+        let mut next = None;
+        let mut next_priority = 0;
+        for (event_time, event) in &self.events {
+            let Event::Message(msg) = event else {
+                return next.or(Some((event_time, event)));
+            };
+            let priority = pct.priorities[&msg.message().destination()];
+            if next.is_none() || priority > next_priority {
+                next = Some((event_time, event));
+                next_priority = priority;
+            }
+        }
+        next
+    }
+
+    /// Removes the event that [`Self::peek`] returned, without searching for
+    /// it again.
+    pub(crate) fn remove(&mut self, event_time: &EventTime) -> Option<Event<M>> {
+        let event = self.events.remove(event_time)?;
+
+        if let (Some(pct), Event::Message(msg)) = (&mut self.pct, &event) {
+            pct.scheduled(msg.message().destination());
+        }
+        if is_message(&event) {
+            self.message_count -= 1;
+        }
+        Some(event)
+    }
+
+    /// Returns whether a message waits for delivery or to be sent.
+    pub(crate) fn has_messages(&self) -> bool {
+        self.message_count > 0
+    }
+
+    /// Returns the pending events in time order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&EventTime, &Event<M>)> {
+        self.events.iter()
+    }
+}
+
+fn is_message<M: ProtocolMessage>(event: &Event<M>) -> bool {
+    matches!(event, Event::Message(_) | Event::Send(_))
+}
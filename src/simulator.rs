@@ -1,50 +1,109 @@
 use itertools::Itertools;
 use rand::prelude::*;
 use rand_chacha::ChaCha8Rng;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use std::{
-    collections::BTreeMap,
+    cell::RefCell,
+    cmp,
     fmt::Debug,
+    rc::Rc,
     time::{Duration, Instant},
 };
 
 use crate::{
+    invariants::ScheduledChecker,
     node::{Node, NodeId},
-    Configuration,
+    observer::SimulationObserver,
+    scheduler::EventQueue,
+    statistics::SimulationStatistics,
+    trace::{DropReason, Trace, TraceEvent},
+    BuildError, CheckSchedule, Client, Configuration, DeliverMessage, ProcessingConfiguration,
+    SimulationView, SimulatorBuilder,
 };
 
 use super::{
-    model::{DeterministicClient, DeterministicNode, InvariantChecker, ProtocolMessage},
-    Network,
+    model::{
+        DeterministicClient, DeterministicNode, InvariantChecker, MembershipChange,
+        MembershipError, ProtocolMessage,
+    },
+    FaultScript, Network, NetworkMetrics, Rule,
 };
 
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
-struct EventTime {
-    time: Instant,
-    // trick: to handle ties in the times, use an offset. We simply
-    // use the event count (number of events processed).
-    offset: usize,
-}
-
 #[derive(Debug, Clone, Eq, PartialEq)]
-enum Event<M: ProtocolMessage> {
+pub enum Event<M: ProtocolMessage> {
+    /// Delivery of a message.
     Message(SimulationMessage<M>),
+    /// Tick of all nodes and clients.
     Tick,
+    /// Tick of a single node (by index) that was postponed because the node
+    /// was busy.
+    NodeTick(usize),
+    /// Messages produced by a node that are sent once it finished processing.
+    Send(Vec<M>),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SimulationMessage<M: ProtocolMessage> {
     message: M,
     id: usize,
+    delivery_id: u64,
 }
 
 impl<M: ProtocolMessage> SimulationMessage<M> {
-    pub fn new(message: M, id: usize) -> Self {
-        SimulationMessage { message, id }
+    pub fn new(message: M, id: usize, delivery_id: u64) -> Self {
+        SimulationMessage {
+            message,
+            id,
+            delivery_id,
+        }
+    }
+
+    pub fn message(&self) -> &M {
+        &self.message
+    }
+
+    pub(crate) fn delivery_id(&self) -> u64 {
+        self.delivery_id
     }
+
+    /// Returns the id of the message. Duplicates share the id of the original.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+/// Result of a single simulation step.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StepResult<M: ProtocolMessage> {
+    /// An event was processed.
+    Processed(ProcessedEvent<M>),
+    /// The simulation stopped without processing an event.
+    Stopped(StopReason),
+}
+
+/// An event processed by the simulator and the messages it caused to be sent.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProcessedEvent<M: ProtocolMessage> {
+    /// Simulated time at which the event was processed.
+    pub time: Duration,
+    pub event: Event<M>,
+    pub sent: Vec<M>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StopReason {
+    /// All clients finished their work.
+    Finished,
+    /// The next event would exceed the maximum simulation time.
+    TimedOut,
+    /// No more events are pending.
+    Exhausted,
+    /// The condition passed to `run_until` was met.
+    Paused,
 }
 
+#[derive(Clone)]
 pub struct Simulator<
     N: DeterministicNode,
     C: DeterministicClient<Message = N::Message>,
@@ -53,23 +112,52 @@ pub struct Simulator<
     start_time: Instant,
     network: Network<N::Message>,
     nodes: Vec<Node<N>>,
-    clients: Vec<C>,
-    events: BTreeMap<EventTime, Event<N::Message>>,
+    clients: Vec<Client<C>>,
+    events: EventQueue<N::Message>,
     config: Configuration,
     rng: ChaCha8Rng,
     elapsed: Duration,
     event_processed_count: usize,
-    total_event_count: usize,
     total_message_count: usize,
     invariant_checker: I,
+    checkers: Vec<ScheduledChecker<N, C>>,
+    // Whether no messages were in flight after the last event.
+    quiescent: bool,
+    settling: Option<Settling>,
+    // Why the simulation stopped, once it did.
+    stopped: Option<StopReason>,
+    statistics: SimulationStatistics,
+    // Rules of fault scripts that are not in effect yet, ordered by time.
+    scheduled_rules: Vec<(Duration, Rule<N::Message>)>,
+    node_factory: Option<Rc<NodeFactory<N>>>,
+    trace: Option<Trace<N::Message>>,
+    observers: Vec<Rc<RefCell<dyn SimulationObserver<N::Message>>>>,
 }
 
+// Progress of the settle phase, see `SettleConfiguration`.
+#[derive(Debug, Clone)]
+struct Settling {
+    start: Duration,
+    // Simulated time since which no messages are in flight.
+    quiet_since: Option<Duration>,
+}
+
+/// Creates a node with the given id when a node requests to spawn a new node.
+pub type NodeFactory<N> = dyn Fn(NodeId) -> N;
+
 impl<
         N: DeterministicNode,
         C: DeterministicClient<Message = N::Message>,
         I: InvariantChecker<N, C>,
     > Simulator<N, C, I>
 {
+    /// Returns a builder to create a simulation step by step.
+    pub fn builder() -> SimulatorBuilder<N, C, I> {
+        SimulatorBuilder::new()
+    }
+
+    /// Creates a simulation. Panics if the configuration or the ids of nodes
+    /// and clients are invalid, see [`Self::try_new`].
     pub fn new(
         start_time: Instant,
         nodes: Vec<N>,
@@ -77,9 +165,22 @@ impl<
         config: Configuration,
         invariant_checker: I,
     ) -> Self {
-        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        Self::try_new(start_time, nodes, clients, config, invariant_checker)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        validate_node_ids(&nodes, &clients);
+    /// Creates a simulation or returns why the configuration or the ids of
+    /// nodes and clients are invalid.
+    pub fn try_new(
+        start_time: Instant,
+        nodes: Vec<N>,
+        clients: Vec<C>,
+        config: Configuration,
+        invariant_checker: I,
+    ) -> Result<Self, BuildError> {
+        config.validate()?;
+        validate_node_ids(&nodes, &clients)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
 
         let replica_count = nodes.len();
         let wrapped_nodes: Vec<Node<N>> = nodes
@@ -90,103 +191,523 @@ impl<
                     config.failure_config.clone(),
                     &mut rng,
                     start_time,
+                    start_time,
                     replica_count,
                 )
             })
             .collect();
 
+        let wrapped_clients: Vec<Client<C>> = clients
+            .into_iter()
+            .map(|client| {
+                Client::new(
+                    client,
+                    config.client_failure_config.clone(),
+                    &mut rng,
+                    start_time,
+                )
+            })
+            .collect();
+
         let nodes = (0..wrapped_nodes.len())
             .map(NodeId::Node)
-            .chain((0..clients.len()).map(NodeId::Client))
+            .chain((0..wrapped_clients.len()).map(NodeId::Client))
             .collect_vec();
         let network = Network::new(start_time, config.network_config.clone(), nodes, &mut rng);
 
-        let events = BTreeMap::from_iter([(
-            EventTime {
-                time: start_time,
-                offset: 0,
-            },
-            Event::Tick,
-        )]);
+        let mut events = EventQueue::new(&config.scheduling, &mut rng);
+        events.push(start_time, Event::Tick, &mut rng);
 
-        Simulator {
+        Ok(Simulator {
             start_time,
             network,
             nodes: wrapped_nodes,
-            clients,
+            clients: wrapped_clients,
             events,
             config,
             rng,
             elapsed: Duration::from_secs(0),
             event_processed_count: 0,
-            total_event_count: 0,
             total_message_count: 0,
             invariant_checker,
-        }
+            checkers: vec![],
+            quiescent: true,
+            settling: None,
+            stopped: None,
+            statistics: SimulationStatistics::default(),
+            scheduled_rules: vec![],
+            node_factory: None,
+            trace: None,
+            observers: vec![],
+        })
     }
 
+    /// Runs the simulation until all clients finished (returns true) or the
+    /// simulation timed out (returns false).
     pub fn run(&mut self) -> bool {
-        while let Some((event_time, event)) = self.events.pop_first() {
-            self.event_processed_count += 1;
-            let now = event_time.time;
-            self.elapsed = now.duration_since(self.start_time);
+        self.run_until(|_| false) == StopReason::Finished
+    }
+
+    /// Runs the simulation until the predicate holds. The predicate is checked
+    /// before every step, so the simulation can be paused just before a
+    /// specific event (see `next_event`).
+    pub fn run_until(&mut self, mut predicate: impl FnMut(&Self) -> bool) -> StopReason {
+        loop {
+            if predicate(self) {
+                return StopReason::Paused;
+            }
+            if let Err(reason) = self.process_next_event(false) {
+                return reason;
+            }
+        }
+    }
+
+    /// Runs the simulation until all events up to the given simulated time
+    /// have been processed.
+    pub fn run_until_time(&mut self, time: Duration) -> StopReason {
+        self.run_until(|simulator| {
+            simulator
+                .next_event()
+                .is_some_and(|(event_time, _)| event_time > time)
+        })
+    }
+
+    /// Processes exactly one event. Once the simulation stopped, it stays
+    /// stopped and every step returns the same reason.
+    pub fn step(&mut self) -> StepResult<N::Message> {
+        match self.process_next_event(true) {
+            Ok(processed) => {
+                StepResult::Processed(processed.expect("Processed events are kept when stepping"))
+            }
+            Err(reason) => StepResult::Stopped(reason),
+        }
+    }
 
-            if now.duration_since(self.start_time) > self.config.max_sim_time {
-                return false;
+    // Processes the next event or returns why the simulation stopped. The
+    // event and the messages it caused are only copied for the caller if
+    // requested, so running the simulation does not pay for them.
+    fn process_next_event(
+        &mut self,
+        keep_event: bool,
+    ) -> Result<Option<ProcessedEvent<N::Message>>, StopReason> {
+        if let Some(reason) = self.stopped {
+            return Err(reason);
+        }
+        let Some((event_time, _)) = self.events.peek() else {
+            return Err(self.stop(StopReason::Exhausted));
+        };
+        let event_time = event_time.clone();
+
+        let next_event_time = event_time.time.duration_since(self.start_time);
+        if let Some(reason) = self.stop_reason(next_event_time) {
+            return Err(self.stop(reason));
+        }
+
+        let event = self.events.remove(&event_time).unwrap();
+        self.event_processed_count += 1;
+        // Some scheduling strategies deliver messages later than their time,
+        // but the clock never goes backwards.
+        let now = cmp::max(event_time.time, self.start_time + self.elapsed);
+        self.elapsed = now.duration_since(self.start_time);
+        self.statistics.set_elapsed(self.elapsed);
+        self.activate_scheduled_rules();
+
+        let kept_event = keep_event.then(|| event.clone());
+        let messages = self.handle_event(now, event);
+        if self
+            .event_processed_count
+            .is_multiple_of(self.config.check_invariants_frequency)
+        {
+            self.check_invariants();
+        }
+
+        let sent = keep_event.then(|| messages.clone());
+        self.send_messages(now, messages);
+        self.run_scheduled_checks();
+        let quiescent = self.settling.is_some() && self.is_quiescent();
+        if let Some(settling) = &mut self.settling {
+            if !quiescent {
+                settling.quiet_since = None;
+            } else if settling.quiet_since.is_none() {
+                settling.quiet_since = Some(self.elapsed);
             }
+        }
+
+        Ok(kept_event.zip(sent).map(|(event, sent)| ProcessedEvent {
+            time: self.elapsed,
+            event,
+            sent,
+        }))
+    }
 
-            if self.clients.iter().all(|client| client.finished()) {
-                self.check_invariants();
-                return true;
+    // Decides whether the simulation stops before the event at the given time.
+    // Once the clients finished, the settle phase starts if configured.
+    fn stop_reason(&mut self, next_event_time: Duration) -> Option<StopReason> {
+        if let Some(settling) = &self.settling {
+            let settle_config = self
+                .config
+                .settle_config
+                .as_ref()
+                .expect("Settling requires a settle configuration");
+            let is_settled = settling.quiet_since.is_some_and(|quiet_since| {
+                next_event_time >= quiet_since + settle_config.quiet_period
+            });
+            if is_settled {
+                return Some(StopReason::Finished);
             }
+            let is_timed_out = next_event_time > settling.start + settle_config.max_settle_time;
+            return is_timed_out.then_some(StopReason::TimedOut);
+        }
+
+        if next_event_time > self.config.max_sim_time {
+            return Some(StopReason::TimedOut);
+        }
+        if !self.clients.iter().all(|client| client.finished()) {
+            return None;
+        }
+        if self.config.settle_config.is_none() {
+            return Some(StopReason::Finished);
+        }
 
-            let messages = self.handle_event(now, event);
-            if self.event_processed_count % self.config.check_invariants_frequency == 0 {
-                self.check_invariants();
+        info!(time = ?self.elapsed, "Clients finished, settling");
+        self.heal();
+        self.settling = Some(Settling {
+            start: self.elapsed,
+            quiet_since: self.is_quiescent().then_some(self.elapsed),
+        });
+        None
+    }
+
+    // Runs the final checks. Later steps return the same reason without
+    // running them again.
+    fn stop(&mut self, reason: StopReason) -> StopReason {
+        self.stopped = Some(reason);
+        info!(time = ?self.elapsed, reason = ?reason, "Simulation stopped\n{}", self.statistics);
+        if reason == StopReason::Finished {
+            self.check_invariants();
+        }
+        for index in 0..self.checkers.len() {
+            if self.checkers[index].is_due_at_end() {
+                self.run_checker(index);
             }
+        }
+        reason
+    }
 
-            for msg in messages {
-                self.total_message_count += 1;
-                let message_id = self.total_message_count;
-                debug!(
-                    time = ?now.duration_since(self.start_time),
-                    from = ?msg.source(),
-                    to = ?msg.destination(),
-                    msg = ?msg,
-                    message_id = message_id,
-                    "Sending message"
-                );
+    fn send_messages(&mut self, now: Instant, messages: Vec<N::Message>) {
+        for msg in messages {
+            self.total_message_count += 1;
+            let message_id = self.total_message_count;
+            debug!(
+                time = ?now.duration_since(self.start_time),
+                from = ?msg.source(),
+                to = ?msg.destination(),
+                msg = ?msg,
+                message_id = message_id,
+                "Sending message"
+            );
 
-                let delivered_msgs = self.network.send(msg, now, &mut self.rng);
-                for del_msg in delivered_msgs {
-                    self.push_event(
-                        now + del_msg.delay,
-                        Event::Message(SimulationMessage::new(del_msg.message, message_id)),
-                    );
-                }
+            self.record(now, || TraceEvent::Send {
+                message_id,
+                message: msg.clone(),
+            });
+            self.statistics.record_send(msg.source(), msg.destination());
+            let delivered_msgs = self.network.send(msg, message_id, now, &mut self.rng);
+            for event in self.network.take_events() {
+                self.record(now, || event);
             }
+            self.put_in_flight(now, delivered_msgs);
+        }
+    }
+
+    fn put_in_flight(&mut self, now: Instant, delivered_msgs: Vec<DeliverMessage<N::Message>>) {
+        for del_msg in delivered_msgs {
+            self.statistics.record_in_flight(del_msg.id, now);
+            self.push_event(
+                now + del_msg.delay,
+                Event::Message(SimulationMessage::new(
+                    del_msg.message,
+                    del_msg.message_id,
+                    del_msg.id,
+                )),
+            );
         }
-        false
     }
 
     fn push_event(&mut self, time: Instant, event: Event<N::Message>) {
-        self.total_event_count += 1;
-        self.events.insert(
-            EventTime {
-                time,
-                offset: self.total_event_count,
-            },
-            event,
-        );
+        self.events.push(time, event, &mut self.rng);
+    }
+
+    /// Creates a copy of the simulation in its current state that continues
+    /// with a different seed. This allows to explore many different futures
+    /// from an interesting state (e.g., right after a leader change) without
+    /// re-running the simulation from the start. Failure and recovery times
+    /// that have already been sampled are kept, so the futures diverge as new
+    /// randomness is drawn. Invariant violations in the fork report the new
+    /// seed.
+    pub fn fork(&self, seed: u64) -> Self
+    where
+        N: Clone,
+        C: Clone,
+        I: Clone,
+    {
+        let mut fork = self.clone();
+        fork.rng = ChaCha8Rng::seed_from_u64(seed);
+        fork.config.seed = seed;
+        fork
     }
 
+    /// Returns the simulated time of the last processed event.
     pub fn elapsed(&self) -> Duration {
         self.elapsed
     }
 
+    pub fn nodes(&self) -> &[Node<N>] {
+        &self.nodes
+    }
+
+    pub fn clients(&self) -> &[Client<C>] {
+        &self.clients
+    }
+
+    /// Returns the pending events in time order, along with their simulated
+    /// time. Depending on the scheduling strategy, events might be processed
+    /// in a different order.
+    pub fn pending_events(&self) -> impl Iterator<Item = (Duration, &Event<N::Message>)> {
+        self.events
+            .iter()
+            .map(|(event_time, event)| (event_time.time.duration_since(self.start_time), event))
+    }
+
+    /// Returns the event that will be processed by the next step.
+    pub fn next_event(&self) -> Option<(Duration, &Event<N::Message>)> {
+        self.events
+            .peek()
+            .map(|(event_time, event)| (event_time.time.duration_since(self.start_time), event))
+    }
+
+    pub fn network_metrics(&self) -> &NetworkMetrics {
+        self.network.metrics()
+    }
+
+    /// Returns the message and request latencies, message counts and
+    /// throughput so far. They are also logged when the simulation stops.
+    pub fn statistics(&self) -> &SimulationStatistics {
+        &self.statistics
+    }
+
+    /// Disables all faults from now on: partitions end, failed links come back
+    /// up and deliver the messages they held back (which keep their message
+    /// id and are not matched against rules again), crashed nodes and clients
+    /// restart, and nothing fails or is duplicated anymore. Latencies,
+    /// processing times and rules stay in effect. Called automatically when
+    /// the settle phase starts, see [`crate::SettleConfiguration`].
+    pub fn heal(&mut self) {
+        let now = self.start_time + self.elapsed;
+        info!(time = ?self.elapsed, "Healing all faults");
+
+        self.config.network_config = self.config.network_config.without_faults();
+        self.config.failure_config.mean_time_between_failures = None;
+        self.config.client_failure_config = None;
+
+        let released_messages = self.network.heal(now, &mut self.rng);
+        for event in self.network.take_events() {
+            self.record(now, || event);
+        }
+        for node_id in 0..self.nodes.len() {
+            let was_down = self.nodes[node_id].is_crashed();
+            self.nodes[node_id].heal(now, &mut self.rng);
+            self.record_state_change(now, NodeId::Node(node_id), was_down, false);
+        }
+        for client_id in 0..self.clients.len() {
+            let was_down = !self.clients[client_id].is_up();
+            self.clients[client_id].heal(now, &mut self.rng);
+            self.record_state_change(now, NodeId::Client(client_id), was_down, false);
+        }
+        self.put_in_flight(now, released_messages);
+    }
+
+    /// Returns whether no messages are in flight: none waits for delivery, to
+    /// be sent by a busy node or for a failed link to recover.
+    pub fn is_quiescent(&self) -> bool {
+        !self.events.has_messages() && !self.network.has_held_messages()
+    }
+
+    /// Adds a rule that intercepts matching messages sent from now on. Rules
+    /// can be added before the simulation starts, while it is paused or by a
+    /// fault script.
+    pub fn add_rule(&mut self, rule: Rule<N::Message>) {
+        self.network.add_rule(rule);
+    }
+
+    /// Adds the rules of the script at their times. Rules whose time has
+    /// passed take effect with the next event.
+    pub fn add_fault_script(&mut self, script: FaultScript<N::Message>) {
+        for (time, rule) in script.into_rules() {
+            let index = self
+                .scheduled_rules
+                .partition_point(|(scheduled, _)| *scheduled <= time);
+            self.scheduled_rules.insert(index, (time, rule));
+        }
+    }
+
+    fn activate_scheduled_rules(&mut self) {
+        let due = self
+            .scheduled_rules
+            .partition_point(|(time, _)| *time <= self.elapsed);
+        for (_, rule) in self.scheduled_rules.drain(..due) {
+            self.network.add_rule(rule);
+        }
+    }
+
+    /// Starts recording sends, deliveries, drops, crashes and network changes
+    /// from now on. See [`Self::trace`].
+    pub fn record_trace(&mut self) {
+        self.trace.get_or_insert_with(Trace::new);
+    }
+
+    /// Returns the events recorded since [`Self::record_trace`] was called.
+    pub fn trace(&self) -> Option<&Trace<N::Message>> {
+        self.trace.as_ref()
+    }
+
+    /// Registers an observer that is notified of everything that happens from
+    /// now on. The caller keeps a handle to the observer to read its results.
+    /// Forks of the simulation share the observers of the original.
+    pub fn add_observer(&mut self, observer: Rc<RefCell<dyn SimulationObserver<N::Message>>>) {
+        self.observers.push(observer);
+    }
+
+    fn is_observed(&self) -> bool {
+        self.trace.is_some() || !self.observers.is_empty()
+    }
+
+    // Passes the event to the observers and the trace. The event is only
+    // created if anyone is interested in it.
+    fn record(&mut self, now: Instant, event: impl FnOnce() -> TraceEvent<N::Message>) {
+        if !self.is_observed() {
+            return;
+        }
+        let time = now.duration_since(self.start_time);
+        let event = event();
+        for observer in &self.observers {
+            event.notify(time, &mut *observer.borrow_mut());
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.push(time, event);
+        }
+    }
+
+    // Records a crash or recovery if the node's state changed while it handled
+    // an event.
+    fn record_state_change(&mut self, now: Instant, id: NodeId, was_down: bool, is_down: bool) {
+        match (was_down, is_down) {
+            (false, true) => self.record(now, || TraceEvent::NodeCrash(id)),
+            (true, false) => {
+                // Requests started before the crash are not measured.
+                self.statistics.record_recovery(id);
+                self.record(now, || TraceEvent::NodeRecover(id));
+            }
+            _ => {}
+        }
+    }
+
+    /// Sets the factory used to create nodes that are spawned by a
+    /// [`MembershipChange::Spawn`] request of a node.
+    pub fn set_node_factory(&mut self, factory: impl Fn(NodeId) -> N + 'static) {
+        self.node_factory = Some(Rc::new(factory));
+    }
+
+    /// Adds a new node to the running simulation. The node is created with the
+    /// next free id, which is returned. Ids of removed nodes are not reused.
+    pub fn add_node(&mut self, make_node: impl FnOnce(NodeId) -> N) -> NodeId {
+        let now = self.start_time + self.elapsed;
+        let id = NodeId::Node(self.nodes.len());
+        let node = make_node(id);
+        assert_eq!(node.id(), id, "Spawned node must use the assigned id");
+
+        info!(
+            time = ?self.elapsed,
+            node = ?id,
+            "Node added"
+        );
+        self.nodes.push(Node::new(
+            node,
+            self.config.failure_config.clone(),
+            &mut self.rng,
+            self.start_time,
+            now,
+            0,
+        ));
+        self.network.add_node(id);
+        self.update_replica_count();
+        id
+    }
+
+    /// Permanently removes a node from the running simulation. Messages to the
+    /// node are dropped from now on. Removing a node twice has no effect.
+    pub fn remove_node(&mut self, id: NodeId) -> Result<(), MembershipError> {
+        let NodeId::Node(node_id) = id else {
+            return Err(MembershipError::NotANode(id));
+        };
+        if node_id >= self.nodes.len() {
+            return Err(MembershipError::UnknownNode(id));
+        }
+        let now = self.start_time + self.elapsed;
+        self.nodes[node_id].decommission(now);
+        self.network.remove_node(id);
+        self.update_replica_count();
+        Ok(())
+    }
+
+    fn update_replica_count(&mut self) {
+        let replica_count = self.members().count();
+        for node in &mut self.nodes {
+            node.set_replica_count(replica_count);
+        }
+    }
+
+    fn apply_membership_changes(&mut self, node_id: usize, changes: Vec<MembershipChange>) {
+        for change in changes {
+            let result = match change {
+                MembershipChange::Spawn => match self.node_factory.clone() {
+                    Some(factory) => {
+                        self.add_node(|id| factory(id));
+                        Ok(())
+                    }
+                    None => Err(MembershipError::MissingNodeFactory),
+                },
+                MembershipChange::Decommission(id) => self.remove_node(id),
+            };
+            if let Err(error) = result {
+                warn!(
+                    time = ?self.elapsed,
+                    node = ?NodeId::Node(node_id),
+                    change = ?change,
+                    "Ignoring membership change: {}",
+                    error
+                );
+            }
+        }
+    }
+
+    fn exists(&self, id: NodeId) -> bool {
+        match id {
+            NodeId::Node(node_id) => node_id < self.nodes.len(),
+            NodeId::Client(client_id) => client_id < self.clients.len(),
+        }
+    }
+
+    // Nodes that have not been removed from the simulation.
+    fn members(&self) -> impl Iterator<Item = &Node<N>> {
+        self.nodes.iter().filter(|n| !n.is_decommissioned())
+    }
+
+    // Whatever the failure configuration, a node only fails if a majority of
+    // the members stays up.
     fn can_additional_node_fail(&self) -> bool {
-        let max_failures = self.nodes.len() / 2;
-        let currently_failed = self.nodes.iter().filter(|n| !n.is_up()).count();
+        let max_failures = self.members().count() / 2;
+        let currently_failed = self.members().filter(|n| !n.is_up()).count();
         currently_failed < max_failures
     }
 
@@ -196,7 +717,54 @@ impl<
                 let SimulationMessage {
                     message: msg,
                     id: message_id,
+                    delivery_id,
                 } = msg;
+
+                if self.network.is_cancelled(delivery_id) {
+                    debug!(
+                        time = ?now.duration_since(self.start_time),
+                        from = ?msg.source(),
+                        to = ?msg.destination(),
+                        message_id = message_id,
+                        "Message dropped from full link queue"
+                    );
+                    self.record(now, || TraceEvent::Drop {
+                        message: msg,
+                        reason: DropReason::Overflow,
+                    });
+                    self.statistics.record_dropped(delivery_id);
+                    return vec![];
+                }
+
+                // e.g., a node that is only spawned later
+                if !self.exists(msg.destination()) {
+                    debug!(
+                        time = ?now.duration_since(self.start_time),
+                        to = ?msg.destination(),
+                        message_id = message_id,
+                        "Message to unknown node dropped"
+                    );
+                    self.record(now, || TraceEvent::Drop {
+                        message: msg,
+                        reason: DropReason::NodeDown,
+                    });
+                    self.statistics.record_dropped(delivery_id);
+                    return vec![];
+                }
+
+                if let NodeId::Node(node_id) = msg.destination() {
+                    if self.nodes[node_id].is_busy(now) {
+                        // The node handles one event at a time, so the message
+                        // waits until the node is done.
+                        let busy_until = self.nodes[node_id].busy_until();
+                        self.push_event(
+                            busy_until,
+                            Event::Message(SimulationMessage::new(msg, message_id, delivery_id)),
+                        );
+                        return vec![];
+                    }
+                }
+
                 debug!(
                     time = ?now.duration_since(self.start_time),
                     from = ?msg.source(),
@@ -206,13 +774,58 @@ impl<
                     "Received message"
                 );
 
-                match msg.destination() {
+                let traced = self.is_observed().then(|| msg.clone());
+                let destination = msg.destination();
+                let (messages, is_down) = match destination {
                     NodeId::Node(node_id) => {
                         let can_fail = self.can_additional_node_fail();
-                        self.nodes[node_id].process_message(msg, now, can_fail, &mut self.rng)
+                        let processing_time = self.nodes[node_id].message_processing_time(&msg);
+                        let node = &mut self.nodes[node_id];
+                        let was_down = node.is_crashed();
+                        let messages = node.process_message(msg, now, can_fail, &mut self.rng);
+                        let is_crashed = node.is_crashed();
+                        let is_down = is_crashed || node.is_decommissioned();
+                        self.record_state_change(now, destination, was_down, is_crashed);
+                        let messages =
+                            self.finish_processing(node_id, now, processing_time, messages);
+                        (messages, is_down)
+                    }
+                    NodeId::Client(client_id) => {
+                        let client = &mut self.clients[client_id];
+                        let was_down = !client.is_up();
+                        let messages = client.process_message(msg, now, &mut self.rng);
+                        let is_down = !client.is_up();
+                        let marks = client.take_request_marks();
+                        // A restarted client forgets its requests before it
+                        // starts new ones.
+                        self.record_state_change(now, destination, was_down, is_down);
+                        self.statistics
+                            .record_request_marks(destination, marks, now);
+                        (messages, is_down)
                     }
-                    NodeId::Client(client_id) => self.clients[client_id].process_message(msg, now),
+                };
+
+                if is_down {
+                    self.statistics.record_dropped(delivery_id);
+                } else {
+                    self.statistics.record_delivery(delivery_id, now);
+                }
+                if let Some(message) = traced {
+                    self.record(now, || {
+                        if is_down {
+                            TraceEvent::Drop {
+                                message,
+                                reason: DropReason::NodeDown,
+                            }
+                        } else {
+                            TraceEvent::Deliver {
+                                message_id,
+                                message,
+                            }
+                        }
+                    });
                 }
+                messages
             }
             Event::Tick => {
                 let mut messages = Vec::new();
@@ -223,42 +836,189 @@ impl<
                 );
 
                 // Handle node ticks
-                for node in &mut self.nodes {
-                    messages.extend(node.tick(now, &mut self.rng));
+                for node_id in 0..self.nodes.len() {
+                    messages.extend(self.tick_node(node_id, now));
                 }
 
                 // Handle client ticks
-                for client in &mut self.clients {
-                    messages.extend(client.tick(now));
+                for client_id in 0..self.clients.len() {
+                    let client = &mut self.clients[client_id];
+                    let was_down = !client.is_up();
+                    messages.extend(client.tick(now, &mut self.rng));
+                    let is_down = !client.is_up();
+                    let marks = client.take_request_marks();
+                    self.record_state_change(now, NodeId::Client(client_id), was_down, is_down);
+                    self.statistics
+                        .record_request_marks(NodeId::Client(client_id), marks, now);
+                }
+
+                for observer in &self.observers {
+                    observer
+                        .borrow_mut()
+                        .on_tick(now.duration_since(self.start_time));
                 }
 
                 self.push_event(now + self.config.tick_interval, Event::Tick);
 
                 messages
             }
+            Event::NodeTick(node_id) => {
+                if self.nodes[node_id].take_deferred_tick() {
+                    self.tick_node(node_id, now)
+                } else {
+                    vec![]
+                }
+            }
+            Event::Send(messages) => messages,
         }
     }
 
-    fn check_invariants(&self) {
-        self.invariant_checker
-            .check_invariants(self.config.seed, &self.nodes, &self.clients);
+    fn tick_node(&mut self, node_id: usize, now: Instant) -> Vec<N::Message> {
+        let node = &mut self.nodes[node_id];
+        if node.is_busy(now) {
+            if node.defer_tick() {
+                let busy_until = node.busy_until();
+                self.push_event(busy_until, Event::NodeTick(node_id));
+            }
+            return vec![];
+        }
+
+        // Ticks take no time unless the node says otherwise, so that
+        // frequent ticks do not keep the node busy.
+        let processing_time = Some(node.tick_processing_time().unwrap_or_default());
+        let was_down = node.is_crashed();
+        let messages = node.tick(now, &mut self.rng);
+        let is_down = node.is_crashed();
+        self.record_state_change(now, NodeId::Node(node_id), was_down, is_down);
+        self.finish_processing(node_id, now, processing_time, messages)
+    }
+
+    /// Keeps the node busy for the processing time of the event it just
+    /// handled. Messages are only sent once processing is done.
+    fn finish_processing(
+        &mut self,
+        node_id: usize,
+        now: Instant,
+        processing_time: Option<Duration>,
+        messages: Vec<N::Message>,
+    ) -> Vec<N::Message> {
+        let processing_time = processing_time.unwrap_or_else(|| self.sample_processing_time());
+        let changes = self.nodes[node_id].take_membership_changes();
+        self.apply_membership_changes(node_id, changes);
+
+        let done = self.nodes[node_id].occupy(now, processing_time);
+        if done <= now || messages.is_empty() {
+            return messages;
+        }
+
+        self.push_event(done, Event::Send(messages));
+        vec![]
+    }
+
+    fn sample_processing_time(&mut self) -> Duration {
+        let ProcessingConfiguration {
+            min_processing_time,
+            max_processing_time,
+        } = self.config.processing_config;
+        if max_processing_time <= min_processing_time {
+            return min_processing_time;
+        }
+        self.rng
+            .gen_range(min_processing_time..=max_processing_time)
+    }
+
+    /// Returns a read-only view of the current state, as passed to invariant
+    /// checkers.
+    pub fn view(&self) -> SimulationView<'_, N, C> {
+        SimulationView::new(
+            self.config.seed,
+            self.start_time,
+            self.elapsed,
+            &self.nodes,
+            &self.clients,
+            &self.events,
+            &self.network,
+        )
+    }
+
+    fn check_invariants(&mut self) {
+        self.invariant_checker.check_view(&self.view());
+        let now = self.start_time + self.elapsed;
+        self.record(now, || TraceEvent::InvariantCheck);
+    }
+
+    /// Adds an invariant checker that runs on its own schedule in addition to
+    /// the invariant checker of the simulation, e.g., cheap invariants after
+    /// every event and expensive ones only at the end. The name identifies the
+    /// checker if it fails. Panics if the schedule checks every 0 events or
+    /// every 0 seconds.
+    pub fn add_invariant_checker(
+        &mut self,
+        name: impl Into<String>,
+        schedule: CheckSchedule,
+        checker: impl InvariantChecker<N, C> + 'static,
+    ) {
+        let name = name.into();
+        assert!(
+            schedule.is_valid(),
+            "Invalid schedule {:?} for invariant checker '{}'",
+            schedule,
+            name
+        );
+        self.checkers
+            .push(ScheduledChecker::new(name, schedule, Rc::new(checker)));
+    }
+
+    fn run_scheduled_checks(&mut self) {
+        let became_quiescent = self.update_quiescence();
+        for index in 0..self.checkers.len() {
+            if self.checkers[index].is_due(
+                self.event_processed_count,
+                self.elapsed,
+                became_quiescent,
+            ) {
+                self.run_checker(index);
+            }
+        }
+    }
+
+    fn run_checker(&mut self, index: usize) {
+        self.checkers[index].check(&self.view());
+        let now = self.start_time + self.elapsed;
+        self.record(now, || TraceEvent::InvariantCheck);
+    }
+
+    // Returns whether the last message in flight was just delivered or dropped.
+    // Only tracked if a checker runs on quiescence.
+    fn update_quiescence(&mut self) -> bool {
+        let is_tracked = self
+            .checkers
+            .iter()
+            .any(|checker| checker.schedule == CheckSchedule::OnQuiescence);
+        if !is_tracked {
+            return false;
+        }
+        let quiescent = self.is_quiescent();
+        let became_quiescent = quiescent && !self.quiescent;
+        self.quiescent = quiescent;
+        became_quiescent
     }
 }
 
-fn validate_node_ids<N: DeterministicNode, C: DeterministicClient>(nodes: &[N], clients: &[C]) {
+pub(crate) fn validate_node_ids<N: DeterministicNode, C: DeterministicClient>(
+    nodes: &[N],
+    clients: &[C],
+) -> Result<(), BuildError> {
     // Validate node IDs are sequential from 0 to n
     let node_ids: Vec<NodeId> = nodes.iter().map(|n| n.id()).collect();
-    assert_eq!(
-        node_ids,
-        (0..node_ids.len()).map(NodeId::Node).collect_vec(),
-        "Node IDs must be sequential starting from 0"
-    );
+    if node_ids != (0..node_ids.len()).map(NodeId::Node).collect_vec() {
+        return Err(BuildError::NonSequentialNodeIds(node_ids));
+    }
 
     // Validate client IDs are sequential from 0 to n
     let client_ids: Vec<NodeId> = clients.iter().map(|c| c.id()).collect();
-    assert_eq!(
-        client_ids,
-        (0..client_ids.len()).map(NodeId::Client).collect_vec(),
-        "Client IDs must be sequential starting from 0"
-    );
+    if client_ids != (0..client_ids.len()).map(NodeId::Client).collect_vec() {
+        return Err(BuildError::NonSequentialClientIds(client_ids));
+    }
+    Ok(())
 }
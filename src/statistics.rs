@@ -0,0 +1,156 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{node::NodeId, LatencyHistogram, RequestMark};
+
+/// Performance data of a simulation run, in simulated time. The statistics
+/// are deterministic for a seed, so protocol variants can be compared by
+/// running them with the same seeds.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationStatistics {
+    delivery_latencies: LatencyHistogram,
+    request_latencies: LatencyHistogram,
+    link_messages: BTreeMap<(NodeId, NodeId), usize>,
+    elapsed: Duration,
+    // Send times of messages in flight, by delivery id.
+    sent_at: HashMap<u64, Instant>,
+    // Start times of requests in progress, by client and request id.
+    request_starts: HashMap<(NodeId, u64), Instant>,
+}
+
+impl SimulationStatistics {
+    /// Returns the time between sending a message and handing it to its
+    /// receiver, including the time it waits for a busy receiver.
+    /// Duplicates are measured separately, dropped messages not at all, and
+    /// messages held back by a failed link from when the link released them.
+    pub fn delivery_latencies(&self) -> &LatencyHistogram {
+        &self.delivery_latencies
+    }
+
+    /// Returns the latencies of client requests, from the start to the end
+    /// mark (see [`crate::DeterministicClient::request_marks`]).
+    pub fn request_latencies(&self) -> &LatencyHistogram {
+        &self.request_latencies
+    }
+
+    /// Returns the number of messages sent per link (source, destination),
+    /// including messages the network dropped.
+    pub fn link_messages(&self) -> &BTreeMap<(NodeId, NodeId), usize> {
+        &self.link_messages
+    }
+
+    /// Returns the number of messages that were sent but neither delivered
+    /// nor dropped yet.
+    pub fn messages_in_flight(&self) -> usize {
+        self.sent_at.len()
+    }
+
+    /// Returns the number of requests that were started but have not ended
+    /// yet.
+    pub fn requests_in_progress(&self) -> usize {
+        self.request_starts.len()
+    }
+
+    pub fn messages_sent(&self) -> usize {
+        self.link_messages.values().sum()
+    }
+
+    /// Returns the simulated time covered by the statistics.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the delivered messages per simulated second.
+    pub fn message_throughput(&self) -> f64 {
+        self.per_second(self.delivery_latencies.count())
+    }
+
+    /// Returns the completed requests per simulated second.
+    pub fn request_throughput(&self) -> f64 {
+        self.per_second(self.request_latencies.count())
+    }
+
+    fn per_second(&self, count: usize) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        count as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub(crate) fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    pub(crate) fn record_send(&mut self, source: NodeId, destination: NodeId) {
+        *self.link_messages.entry((source, destination)).or_default() += 1;
+    }
+
+    pub(crate) fn record_in_flight(&mut self, delivery_id: u64, now: Instant) {
+        self.sent_at.insert(delivery_id, now);
+    }
+
+    pub(crate) fn record_dropped(&mut self, delivery_id: u64) {
+        self.sent_at.remove(&delivery_id);
+    }
+
+    pub(crate) fn record_delivery(&mut self, delivery_id: u64, now: Instant) {
+        if let Some(sent_at) = self.sent_at.remove(&delivery_id) {
+            self.delivery_latencies.record(now.duration_since(sent_at));
+        }
+    }
+
+    /// Forgets the requests a client started before it crashed.
+    pub(crate) fn record_recovery(&mut self, client: NodeId) {
+        self.request_starts
+            .retain(|(started_by, _), _| *started_by != client);
+    }
+
+    /// Only requests that end are measured.
+    pub(crate) fn record_request_marks(
+        &mut self,
+        client: NodeId,
+        marks: Vec<RequestMark>,
+        now: Instant,
+    ) {
+        for mark in marks {
+            match mark {
+                RequestMark::Start(request_id) => {
+                    self.request_starts.insert((client, request_id), now);
+                }
+                RequestMark::End(request_id) => {
+                    if let Some(start) = self.request_starts.remove(&(client, request_id)) {
+                        self.request_latencies.record(now.duration_since(start));
+                    }
+                }
+                RequestMark::Cancel(request_id) => {
+                    self.request_starts.remove(&(client, request_id));
+                }
+            }
+        }
+    }
+}
+
+/// Summarizes the latencies and throughput, one line each.
+impl fmt::Display for SimulationStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "elapsed: {:?}", self.elapsed)?;
+        writeln!(
+            f,
+            "messages: sent={} delivered={} ({:.1}/s)",
+            self.messages_sent(),
+            self.delivery_latencies.count(),
+            self.message_throughput()
+        )?;
+        writeln!(f, "delivery latency: {}", self.delivery_latencies)?;
+        writeln!(
+            f,
+            "requests: completed={} ({:.1}/s)",
+            self.request_latencies.count(),
+            self.request_throughput()
+        )?;
+        write!(f, "request latency: {}", self.request_latencies)
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        Configuration, FailureConfiguration, LatencyDistribution, NetworkConfig,
+        ProcessingConfiguration,
+    };
+
+    #[test]
+    fn test_default_configuration_is_valid() {
+        assert_eq!(Configuration::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        for config in [
+            Configuration::reliable(),
+            Configuration::lan(),
+            Configuration::wan(),
+            Configuration::flaky(),
+            Configuration::partition_heavy(),
+            Configuration::crash_heavy(),
+            Configuration::chaos(),
+        ] {
+            assert_eq!(config.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_invalid_fields_are_listed() {
+        let config = Configuration {
+            check_invariants_frequency: 0,
+            network_config: NetworkConfig {
+                min_message_latency: Duration::from_millis(200),
+                max_message_latency: Duration::from_millis(100),
+                latency_distribution: LatencyDistribution::Exponential { rate: 0.0 },
+                duplicate_probability: 1.5,
+                ..NetworkConfig::default()
+            },
+            client_failure_config: Some(FailureConfiguration {
+                mean_time_between_failures: Some(Duration::ZERO),
+                mean_time_to_recover: Duration::ZERO,
+            }),
+            processing_config: ProcessingConfiguration {
+                min_processing_time: Duration::from_millis(10),
+                max_processing_time: Duration::ZERO,
+            },
+            ..Configuration::default()
+        };
+
+        let error = config.validate().unwrap_err();
+        let fields = error
+            .invalid_fields
+            .iter()
+            .map(|invalid| invalid.field.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                "check_invariants_frequency",
+                "network_config.min_message_latency",
+                "network_config.latency_distribution.rate",
+                "network_config.duplicate_probability",
+                "client_failure_config.mean_time_between_failures",
+                "client_failure_config.mean_time_to_recover",
+                "processing_config.min_processing_time",
+            ]
+        );
+        assert!(error
+            .to_string()
+            .contains("network_config.duplicate_probability: must be between 0 and 1"));
+    }
+
+    #[test]
+    fn test_recovery_time_only_checked_with_failures() {
+        let config = Configuration {
+            failure_config: FailureConfiguration {
+                mean_time_between_failures: None,
+                mean_time_to_recover: Duration::ZERO,
+            },
+            ..Configuration::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        config_file::{format_duration, parse_duration},
+        Configuration, LatencyDistribution, OverflowPolicy, SchedulingStrategy,
+    };
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_duration("50ms"), Ok(Duration::from_millis(50)));
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("0.3s"), Ok(Duration::from_millis(300)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("10us"), Ok(Duration::from_micros(10)));
+        assert!(parse_duration("50").is_err());
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("5 days").is_err());
+
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::ZERO), "0ms");
+    }
+
+    #[test]
+    fn test_load_toml() {
+        let config = Configuration::from_toml(
+            r#"
+            seed = 42
+            max_sim_time = "1m"
+
+            [network_config]
+            max_message_latency = "250ms"
+            latency_distribution = { type = "normal", mean = 0.5, std_dev = 0.1 }
+            mean_time_between_partitions = "never"
+            queue_overflow_policy = "drop_head"
+
+            [client_failure_config]
+            mean_time_to_recover = "2s"
+
+            [scheduling]
+            type = "pct"
+            depth = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.seed, 42);
+        assert_eq!(config.max_sim_time, Duration::from_secs(60));
+        // unspecified fields keep their defaults
+        assert_eq!(config.tick_interval, Duration::from_millis(50));
+        let network = &config.network_config;
+        assert_eq!(network.max_message_latency, Duration::from_millis(250));
+        assert_eq!(
+            network.latency_distribution,
+            LatencyDistribution::Normal {
+                mean: 0.5,
+                std_dev: 0.1
+            }
+        );
+        assert_eq!(network.mean_time_between_partitions, None);
+        assert_eq!(
+            network.mean_time_between_link_failures,
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(network.queue_overflow_policy, OverflowPolicy::DropHead);
+        let client_failures = config.client_failure_config.unwrap();
+        assert_eq!(client_failures.mean_time_to_recover, Duration::from_secs(2));
+        assert!(
+            matches!(config.scheduling, SchedulingStrategy::Pct(pct) if pct.depth == 2 && pct.max_steps == 10_000)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = Configuration::default();
+        config.network_config.mean_time_between_link_failures = None;
+        config.network_config.link_queue_capacity = Some(3);
+
+        let toml = config.to_toml().unwrap();
+        assert!(toml.contains("mean_time_between_link_failures = \"never\""));
+        let loaded = Configuration::from_toml(&toml).unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", config));
+
+        let json = Configuration::from_json(
+            r#"{"seed": 7, "network_config": {"latency_distribution": {"type": "uniform"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(json.seed, 7);
+        assert_eq!(
+            json.network_config.latency_distribution,
+            LatencyDistribution::Uniform
+        );
+    }
+
+    #[test]
+    fn test_large_seed() {
+        for seed in [0, i64::MAX as u64, i64::MAX as u64 + 1, u64::MAX] {
+            let config = Configuration {
+                seed,
+                ..Configuration::default()
+            };
+            let loaded = Configuration::from_toml(&config.to_toml().unwrap()).unwrap();
+            assert_eq!(loaded.seed, seed);
+        }
+
+        let json = Configuration::from_json(&format!(r#"{{"seed": {}}}"#, u64::MAX)).unwrap();
+        assert_eq!(json.seed, u64::MAX);
+        assert!(Configuration::from_toml("seed = -1").is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let error = Configuration::from_toml("tick_intervall = \"50ms\"").unwrap_err();
+        assert!(error.to_string().contains("tick_intervall"));
+
+        let error = Configuration::from_toml("tick_interval = \"50\"").unwrap_err();
+        assert!(error.to_string().contains("Invalid duration '50'"));
+    }
+}
@@ -4,15 +4,22 @@ mod tests {
     use tracing_subscriber::FmtSubscriber;
 
     use crate::{
-        node::NodeId, Configuration, DeterministicClient, DeterministicNode, InvariantChecker,
-        NetworkConfig, Node, ProtocolMessage, Simulator,
+        cover, node::NodeId, BuildError, CheckSchedule, Client, Configuration, CoverageExplorer,
+        DeterministicClient, DeterministicNode, DiagramFormat, DropReason, Event,
+        FailureConfiguration, FaultScript, InvariantChecker, LinkState, MembershipError,
+        ModelChecker, ModelCheckerConfig, NetworkConfig, NetworkMetrics, Node, OverflowPolicy,
+        PctConfiguration, ProcessingConfiguration, ProtocolMessage, Rule, RuleAction,
+        SchedulingStrategy, SettleConfiguration, SimulationObserver, SimulationView, Simulator,
+        StepResult, StopReason, Sweep, Trace, TraceEvent,
     };
     use std::{
-        collections::HashSet,
+        cell::{Cell, RefCell},
+        collections::BTreeSet,
+        rc::Rc,
         time::{Duration, Instant},
     };
 
-    #[derive(Debug, Clone, Eq, PartialEq)]
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
     enum EchoMessage {
         Request { id: u64, data: String },
         Response { id: u64, data: String },
@@ -34,10 +41,10 @@ mod tests {
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Hash)]
     struct EchoServer {
         id: NodeId,
-        replied_requests: HashSet<u64>,
+        replied_requests: BTreeSet<u64>,
     }
 
     impl DeterministicNode for EchoServer {
@@ -68,15 +75,16 @@ mod tests {
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Hash)]
     struct EchoClient {
         id: NodeId,
         current_request: u64,
         total_requests: u64,
-        completed_requests: HashSet<u64>,
+        completed_requests: BTreeSet<u64>,
         last_request_time: Option<Instant>,
         retry_interval: Duration,
         with_retries: bool,
+        idle_when_finished: bool,
     }
 
     impl EchoClient {
@@ -85,12 +93,19 @@ mod tests {
                 id: NodeId::Client(0),
                 current_request: 0,
                 total_requests,
-                completed_requests: HashSet::new(),
+                completed_requests: BTreeSet::new(),
                 last_request_time: None,
                 retry_interval,
                 with_retries,
+                idle_when_finished: false,
             }
         }
+
+        // Sends nothing once finished, so that the simulation can settle.
+        fn idle_when_finished(mut self) -> Self {
+            self.idle_when_finished = true;
+            self
+        }
     }
 
     impl DeterministicClient for EchoClient {
@@ -102,6 +117,9 @@ mod tests {
 
         fn tick(&mut self, now: Instant) -> Vec<Self::Message> {
             let mut messages = Vec::new();
+            if self.idle_when_finished && self.finished() {
+                return messages;
+            }
 
             // Send next request
             if (self.completed_requests.contains(&self.current_request)
@@ -143,14 +161,24 @@ mod tests {
         fn finished(&self) -> bool {
             self.completed_requests.len() as u64 == self.total_requests
         }
+
+        fn recover(&mut self, now: Instant, _nonce: u64) {
+            // the pending request is sent again with the next retry
+            self.last_request_time = Some(now);
+        }
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     struct EchoInvariantChecker;
 
     impl InvariantChecker<EchoServer, EchoClient> for EchoInvariantChecker {
-        fn check_invariants(&self, seed: u64, nodes: &[Node<EchoServer>], clients: &[EchoClient]) {
-            let client = &clients[0];
+        fn check_invariants(
+            &self,
+            seed: u64,
+            nodes: &[Node<EchoServer>],
+            clients: &[Client<EchoClient>],
+        ) {
+            let client = clients[0].client();
             let server = &nodes[0];
 
             for request_id in &client.completed_requests {
@@ -174,29 +202,45 @@ mod tests {
         }
     }
 
-    fn test_echo_protocol(network_config: NetworkConfig, total_requests: u64, with_retries: bool) {
-        let start_time = Instant::now();
-
-        let server = EchoServer {
-            id: NodeId::Node(0),
-            replied_requests: HashSet::new(),
-        };
-
-        let client = EchoClient::new(total_requests, Duration::from_millis(200), with_retries);
-
-        let config = Configuration {
+    fn echo_config(network_config: NetworkConfig) -> Configuration {
+        Configuration {
             tick_interval: Duration::from_millis(50),
             max_sim_time: Duration::from_secs(30),
             seed: 1,
             check_invariants_frequency: 1,
             network_config,
             ..Configuration::default()
+        }
+    }
+
+    // Network without link failures, partitions or duplicates, for tests that
+    // inject the faults they are about.
+    fn fault_free_network() -> NetworkConfig {
+        NetworkConfig::default().without_faults()
+    }
+
+    fn echo_simulator(
+        config: Configuration,
+        total_requests: u64,
+        with_retries: bool,
+    ) -> Simulator<EchoServer, EchoClient, EchoInvariantChecker> {
+        let start_time = Instant::now();
+
+        let server = EchoServer {
+            id: NodeId::Node(0),
+            replied_requests: BTreeSet::new(),
         };
 
+        let client = EchoClient::new(total_requests, Duration::from_millis(200), with_retries);
+
         let checker = EchoInvariantChecker {};
 
-        let mut simulator = Simulator::new(start_time, vec![server], vec![client], config, checker);
+        Simulator::new(start_time, vec![server], vec![client], config, checker)
+    }
 
+    fn test_echo_protocol(network_config: NetworkConfig, total_requests: u64, with_retries: bool) {
+        let mut simulator =
+            echo_simulator(echo_config(network_config), total_requests, with_retries);
         assert!(simulator.run());
     }
 
@@ -227,4 +271,1060 @@ mod tests {
         let config = NetworkConfig::default();
         test_echo_protocol(config, 10, true);
     }
+
+    #[test]
+    fn test_bounded_link_queues() {
+        let config = NetworkConfig {
+            duplicate_probability: 0.5,
+            link_queue_capacity: Some(1),
+            queue_overflow_policy: OverflowPolicy::DropHead,
+            ..fault_free_network()
+        };
+        let mut simulator = echo_simulator(echo_config(config), 10, true);
+
+        assert!(simulator.run());
+        let metrics = simulator.network_metrics();
+        assert!(metrics.overflow_drops > 0);
+        assert_eq!(metrics.link_failure_drops, 0);
+        assert_eq!(metrics.partition_drops, 0);
+    }
+
+    #[test]
+    fn test_processing_time() {
+        let config = Configuration {
+            processing_config: ProcessingConfiguration {
+                min_processing_time: Duration::from_millis(200),
+                max_processing_time: Duration::from_millis(200),
+            },
+            ..echo_config(fault_free_network())
+        };
+        let mut simulator = echo_simulator(config, 10, true);
+        simulator.record_trace();
+        // the first request and its retries all arrive at 1s
+        simulator.add_rule(Rule::new(
+            |_, _, msg, _| matches!(msg, EchoMessage::Request { id: 1, .. }),
+            RuleAction::HoldUntil(Duration::from_secs(1)),
+        ));
+
+        assert!(simulator.run());
+        let trace = simulator.trace().unwrap();
+        let held = trace
+            .entries()
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.event,
+                    TraceEvent::Send {
+                        message: EchoMessage::Request { id: 1, .. },
+                        ..
+                    }
+                ) && entry.time < Duration::from_secs(1)
+            })
+            .count();
+        assert!(held > 1);
+        // the server handles them one after the other, although it ticks in
+        // between
+        let deliveries = trace
+            .entries()
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.event,
+                    TraceEvent::Deliver {
+                        message: EchoMessage::Request { id: 1, .. },
+                        ..
+                    }
+                )
+            })
+            .map(|entry| entry.time)
+            .take(held)
+            .collect::<Vec<_>>();
+        let expected = (0..held as u32)
+            .map(|index| Duration::from_secs(1) + Duration::from_millis(200) * index)
+            .collect::<Vec<_>>();
+        assert_eq!(deliveries, expected);
+    }
+
+    #[test]
+    fn test_client_failures() {
+        let network_config = NetworkConfig {
+            mean_time_between_link_failures: None,
+            mean_time_between_partitions: None,
+            ..NetworkConfig::default()
+        };
+        let config = Configuration {
+            client_failure_config: Some(FailureConfiguration {
+                mean_time_between_failures: Some(Duration::from_millis(500)),
+                mean_time_to_recover: Duration::from_millis(200),
+            }),
+            ..echo_config(network_config)
+        };
+        let mut simulator = echo_simulator(config, 10, true);
+
+        assert!(simulator.run());
+    }
+
+    // Returns the average time the node or client was down, from each crash
+    // to the following recovery.
+    fn mean_downtime(trace: &Trace<EchoMessage>, id: NodeId) -> Duration {
+        let mut crashed_at = None;
+        let mut downtimes = vec![];
+        for entry in trace.entries() {
+            match entry.event {
+                TraceEvent::NodeCrash(crashed) if crashed == id => crashed_at = Some(entry.time),
+                TraceEvent::NodeRecover(recovered) if recovered == id => {
+                    downtimes.push(entry.time - crashed_at.take().unwrap());
+                }
+                _ => {}
+            }
+        }
+        assert!(
+            downtimes.len() >= 10,
+            "too few crashes: {}",
+            downtimes.len()
+        );
+        downtimes.iter().sum::<Duration>() / downtimes.len() as u32
+    }
+
+    // Runs echo servers (only the first one answers) and a client that never
+    // finishes until the simulated time is up, recording a trace.
+    fn run_cluster(
+        config: Configuration,
+        node_count: usize,
+    ) -> Simulator<EchoServer, EchoClient, EchoInvariantChecker> {
+        let servers = (0..node_count)
+            .map(|id| EchoServer {
+                id: NodeId::Node(id),
+                replied_requests: BTreeSet::new(),
+            })
+            .collect();
+        let client = EchoClient::new(u64::MAX, Duration::from_millis(200), true);
+        let mut simulator = Simulator::new(
+            Instant::now(),
+            servers,
+            vec![client],
+            config,
+            EchoInvariantChecker,
+        );
+        simulator.record_trace();
+        assert_eq!(simulator.run_until(|_| false), StopReason::TimedOut);
+        simulator
+    }
+
+    #[test]
+    fn test_recovery_time() {
+        let failure_config = FailureConfiguration {
+            mean_time_between_failures: Some(Duration::from_secs(5)),
+            mean_time_to_recover: Duration::from_secs(1),
+        };
+        let config = Configuration {
+            max_sim_time: Duration::from_secs(300),
+            check_invariants_frequency: 1000,
+            failure_config: failure_config.clone(),
+            client_failure_config: Some(failure_config),
+            ..echo_config(NetworkConfig::default())
+        };
+        // Nodes only fail if a majority stays up.
+        let simulator = run_cluster(config, 3);
+
+        // failures are only noticed with the next event, i.e., the next tick
+        let trace = simulator.trace().unwrap();
+        for id in [NodeId::Node(0), NodeId::Client(0)] {
+            let downtime = mean_downtime(trace, id);
+            assert!(
+                downtime > Duration::from_millis(700) && downtime < Duration::from_millis(1500),
+                "{} was down for {:?} on average",
+                id,
+                downtime
+            );
+        }
+    }
+
+    #[test]
+    fn test_crash_heavy_preset() {
+        let config = Configuration {
+            tick_interval: Duration::from_millis(50),
+            max_sim_time: Duration::from_secs(120),
+            check_invariants_frequency: 1000,
+            ..Configuration::crash_heavy()
+        };
+        let simulator = run_cluster(config, 3);
+
+        let trace = simulator.trace().unwrap();
+        let downtime = mean_downtime(trace, NodeId::Node(0));
+        assert!(
+            downtime > Duration::from_millis(300) && downtime < Duration::from_millis(800),
+            "node was down for {:?} on average",
+            downtime
+        );
+        // at most one of the three nodes is down at any time
+        let mut down = 0;
+        for entry in trace.entries() {
+            match entry.event {
+                TraceEvent::NodeCrash(NodeId::Node(_)) => down += 1,
+                TraceEvent::NodeRecover(NodeId::Node(_)) => down -= 1,
+                _ => {}
+            }
+            assert!(down <= 1);
+        }
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        let config = Configuration {
+            tick_interval: Duration::ZERO,
+            ..echo_config(NetworkConfig::default())
+        };
+        let server = EchoServer {
+            id: NodeId::Node(0),
+            replied_requests: BTreeSet::new(),
+        };
+        let client = EchoClient::new(1, Duration::from_millis(200), true);
+
+        let result = Simulator::try_new(
+            Instant::now(),
+            vec![server],
+            vec![client],
+            config,
+            EchoInvariantChecker {},
+        );
+        let Some(BuildError::InvalidConfiguration(error)) = result.err() else {
+            panic!("Expected an invalid configuration");
+        };
+        assert_eq!(error.invalid_fields[0].field, "tick_interval");
+    }
+
+    #[test]
+    fn test_builder() {
+        let observer = Rc::new(RefCell::new(CountingObserver::default()));
+        let mut simulator = Simulator::builder()
+            .node(EchoServer {
+                id: NodeId::Node(0),
+                replied_requests: BTreeSet::new(),
+            })
+            .client(EchoClient::new(10, Duration::from_millis(200), true))
+            .config(echo_config(NetworkConfig::default()))
+            .seed(3)
+            .invariant_checker(EchoInvariantChecker {})
+            .observer(observer.clone())
+            .rule(Rule::new(|_, _, _, _| true, RuleAction::Drop).limit(1))
+            .fault_script(FaultScript::new().rule_at(
+                Duration::from_millis(100),
+                Rule::new(|_, _, _, _| true, RuleAction::Drop).limit(1),
+            ))
+            .record_trace()
+            .build()
+            .unwrap();
+
+        assert!(simulator.run());
+        assert!(observer.borrow().sends > 0);
+        assert!(simulator.trace().is_some());
+        assert_eq!(simulator.network_metrics().rule_drops, 2);
+
+        let result = Simulator::builder()
+            .node(EchoServer {
+                id: NodeId::Node(1),
+                replied_requests: BTreeSet::new(),
+            })
+            .client(EchoClient::new(10, Duration::from_millis(200), true))
+            .invariant_checker(EchoInvariantChecker {})
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::NonSequentialNodeIds(vec![NodeId::Node(1)]))
+        );
+
+        let result = Simulator::<EchoServer, EchoClient, EchoInvariantChecker>::builder().build();
+        assert_eq!(result.err(), Some(BuildError::MissingInvariantChecker));
+    }
+
+    // Counts how often it was checked.
+    struct CountingChecker(Rc<Cell<usize>>);
+
+    impl InvariantChecker<EchoServer, EchoClient> for CountingChecker {
+        fn check_invariants(
+            &self,
+            _seed: u64,
+            _nodes: &[Node<EchoServer>],
+            _clients: &[Client<EchoClient>],
+        ) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    struct FailingChecker;
+
+    impl InvariantChecker<EchoServer, EchoClient> for FailingChecker {
+        fn check_invariants(
+            &self,
+            _seed: u64,
+            _nodes: &[Node<EchoServer>],
+            _clients: &[Client<EchoClient>],
+        ) {
+            panic!("Replies diverged");
+        }
+    }
+
+    #[test]
+    fn test_scheduled_checkers() {
+        let counts: [Rc<Cell<usize>>; 4] = Default::default();
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+        simulator.add_invariant_checker(
+            "every 10 events",
+            CheckSchedule::EveryEvents(10),
+            CountingChecker(counts[0].clone()),
+        );
+        simulator.add_invariant_checker(
+            "every second",
+            CheckSchedule::EveryTime(Duration::from_secs(1)),
+            CountingChecker(counts[1].clone()),
+        );
+        simulator.add_invariant_checker(
+            "on quiescence",
+            CheckSchedule::OnQuiescence,
+            CountingChecker(counts[2].clone()),
+        );
+        simulator.add_invariant_checker(
+            "at end",
+            CheckSchedule::AtEnd,
+            CountingChecker(counts[3].clone()),
+        );
+
+        let mut events = 0;
+        while let StepResult::Processed(_) = simulator.step() {
+            events += 1;
+        }
+        simulator.step();
+
+        assert_eq!(counts[0].get(), events / 10);
+        let seconds = simulator.elapsed().as_secs() as usize;
+        assert!(counts[1].get() >= seconds.min(1) && counts[1].get() <= seconds);
+        assert!(counts[2].get() > 0);
+        assert_eq!(counts[3].get(), 1);
+    }
+
+    #[test]
+    fn test_steps_after_stop() {
+        let count = Rc::new(Cell::new(0));
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+        simulator.add_invariant_checker(
+            "at end",
+            CheckSchedule::AtEnd,
+            CountingChecker(count.clone()),
+        );
+
+        assert!(simulator.run());
+        assert_eq!(count.get(), 1);
+        let elapsed = simulator.elapsed();
+        for _ in 0..2 {
+            assert!(matches!(
+                simulator.step(),
+                StepResult::Stopped(StopReason::Finished)
+            ));
+        }
+        assert!(simulator.run());
+        assert_eq!(count.get(), 1);
+        assert_eq!(simulator.elapsed(), elapsed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invariant checker 'replicas agree' failed")]
+    fn test_scheduled_checker_failure() {
+        let mut simulator = Simulator::builder()
+            .node(EchoServer {
+                id: NodeId::Node(0),
+                replied_requests: BTreeSet::new(),
+            })
+            .client(EchoClient::new(10, Duration::from_millis(200), true))
+            .config(echo_config(NetworkConfig::default()))
+            .invariant_checker(())
+            .checker("replicas agree", CheckSchedule::AtEnd, FailingChecker)
+            .build()
+            .unwrap();
+        simulator.run();
+    }
+
+    // Checks that the view of the network is consistent and remembers whether
+    // it saw a partition and messages in flight.
+    #[derive(Clone, Default)]
+    struct NetworkChecker {
+        saw_partition: Rc<Cell<bool>>,
+        saw_pending_delivery: Rc<Cell<bool>>,
+    }
+
+    impl InvariantChecker<EchoServer, EchoClient> for NetworkChecker {
+        fn check_invariants(
+            &self,
+            _seed: u64,
+            _nodes: &[Node<EchoServer>],
+            _clients: &[Client<EchoClient>],
+        ) {
+            unreachable!()
+        }
+
+        fn check_view(&self, view: &SimulationView<'_, EchoServer, EchoClient>) {
+            let server = NodeId::Node(0);
+            let client = NodeId::Client(0);
+
+            for delivery in view.pending_deliveries() {
+                assert!(delivery.time >= view.time());
+                self.saw_pending_delivery.set(true);
+            }
+            if view.is_partitioned(server, client) {
+                assert_eq!(view.partitions().len(), 2);
+                self.saw_partition.set(true);
+            }
+            for (a, b, _) in view.links() {
+                assert_eq!((a, b), (server, client));
+            }
+            assert_eq!(view.is_up(server), view.nodes()[0].is_up());
+        }
+    }
+
+    #[test]
+    fn test_simulation_view() {
+        let checker = NetworkChecker::default();
+        let mut simulator = Simulator::new(
+            Instant::now(),
+            vec![EchoServer {
+                id: NodeId::Node(0),
+                replied_requests: BTreeSet::new(),
+            }],
+            vec![EchoClient::new(10, Duration::from_millis(200), true)],
+            Configuration {
+                max_sim_time: Duration::from_secs(60),
+                ..Configuration::partition_heavy()
+            },
+            checker.clone(),
+        );
+
+        assert!(simulator.run());
+        assert!(checker.saw_partition.get());
+        assert!(checker.saw_pending_delivery.get());
+        // partition_heavy has no link failures
+        assert!(matches!(
+            simulator
+                .view()
+                .link_state(NodeId::Client(0), NodeId::Node(0)),
+            Some(LinkState::Up { .. })
+        ));
+    }
+
+    // Checks that the simulation settled: no faults and nothing in flight.
+    struct SettledChecker(Rc<Cell<bool>>);
+
+    impl InvariantChecker<EchoServer, EchoClient> for SettledChecker {
+        fn check_invariants(
+            &self,
+            _seed: u64,
+            _nodes: &[Node<EchoServer>],
+            _clients: &[Client<EchoClient>],
+        ) {
+            unreachable!()
+        }
+
+        fn check_view(&self, view: &SimulationView<'_, EchoServer, EchoClient>) {
+            assert!(view.is_up(NodeId::Node(0)) && view.is_up(NodeId::Client(0)));
+            assert!(view.partitions().is_empty());
+            assert!(view
+                .links()
+                .iter()
+                .all(|(_, _, state)| matches!(state, LinkState::Up { .. })));
+            assert!(view.pending_deliveries().is_empty());
+            self.0.set(true);
+        }
+    }
+
+    #[test]
+    fn test_settle() {
+        let settled = Rc::new(Cell::new(false));
+        let mut simulator = Simulator::builder()
+            .node(EchoServer {
+                id: NodeId::Node(0),
+                replied_requests: BTreeSet::new(),
+            })
+            .client(EchoClient::new(10, Duration::from_millis(200), true).idle_when_finished())
+            .config(Configuration {
+                max_sim_time: Duration::from_secs(60),
+                settle_config: Some(SettleConfiguration {
+                    quiet_period: Duration::from_millis(500),
+                    max_settle_time: Duration::from_secs(5),
+                }),
+                ..Configuration::chaos()
+            })
+            .invariant_checker(EchoInvariantChecker)
+            .checker(
+                "settled",
+                CheckSchedule::AtEnd,
+                SettledChecker(settled.clone()),
+            )
+            .build()
+            .unwrap();
+
+        assert!(simulator.run());
+        assert!(simulator.is_quiescent());
+        assert!(settled.get());
+    }
+
+    #[test]
+    fn test_heal_releases_held_messages() {
+        let network_config = NetworkConfig {
+            mean_time_between_link_failures: Some(Duration::from_millis(200)),
+            mean_link_recovery_time: Duration::from_secs(1000),
+            hold_probability: 1.0,
+            ..fault_free_network()
+        };
+        let mut simulator = echo_simulator(echo_config(network_config), 10, false);
+        simulator.record_trace();
+        let held_messages = |simulator: &Simulator<_, _, _>| {
+            simulator
+                .view()
+                .links()
+                .into_iter()
+                .flat_map(|(_, _, state)| match state {
+                    LinkState::TempHold {
+                        queued_messages, ..
+                    } => queued_messages.iter().cloned().collect(),
+                    _ => vec![],
+                })
+                .collect::<Vec<(usize, EchoMessage)>>()
+        };
+        simulator.run_until(|simulator| !held_messages(simulator).is_empty());
+        let held = held_messages(&simulator);
+        assert!(!held.is_empty());
+
+        simulator.heal();
+        assert!(simulator.run());
+
+        // held messages are delivered with their original id, without being
+        // sent again
+        let trace = simulator.trace().unwrap();
+        let sends = trace
+            .entries()
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                TraceEvent::Send {
+                    message_id,
+                    message,
+                } => Some((*message_id, message.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sends.len(), simulator.statistics().messages_sent());
+        for (message_id, message) in held {
+            assert_eq!(sends.iter().filter(|(_, sent)| *sent == message).count(), 1);
+            assert!(trace.entries().iter().any(|entry| matches!(
+                &entry.event,
+                TraceEvent::Deliver { message_id: delivered, .. } if *delivered == message_id
+            )));
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        let mut simulator = echo_simulator(
+            Configuration {
+                seed: 1,
+                ..Configuration::reliable()
+            },
+            10,
+            false,
+        );
+        assert!(simulator.run());
+        assert_eq!(simulator.network_metrics(), &NetworkMetrics::default());
+
+        for config in [
+            Configuration::wan(),
+            Configuration::flaky(),
+            Configuration::chaos(),
+        ] {
+            let mut simulator = echo_simulator(
+                Configuration {
+                    max_sim_time: Duration::from_secs(60),
+                    ..config
+                },
+                10,
+                true,
+            );
+            assert!(simulator.run());
+        }
+    }
+
+    #[test]
+    fn test_dynamic_membership() {
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+
+        let id = simulator.add_node(|id| EchoServer {
+            id,
+            replied_requests: BTreeSet::new(),
+        });
+        assert_eq!(id, NodeId::Node(1));
+        assert_eq!(simulator.remove_node(id), Ok(()));
+        assert_eq!(
+            simulator.remove_node(NodeId::Client(0)),
+            Err(MembershipError::NotANode(NodeId::Client(0)))
+        );
+        assert_eq!(
+            simulator.remove_node(NodeId::Node(2)),
+            Err(MembershipError::UnknownNode(NodeId::Node(2)))
+        );
+
+        assert!(simulator.run());
+    }
+
+    #[test]
+    fn test_step_api() {
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+
+        // the first event is the initial tick, which sends the first request
+        let StepResult::Processed(first) = simulator.step() else {
+            panic!("expected an event to be processed");
+        };
+        assert_eq!(first.event, Event::Tick);
+        assert_eq!(first.sent.len(), 1);
+
+        assert_eq!(
+            simulator.run_until_time(Duration::from_secs(1)),
+            StopReason::Paused
+        );
+        assert!(simulator.elapsed() <= Duration::from_secs(1));
+        assert!(simulator.next_event().unwrap().0 > Duration::from_secs(1));
+
+        // pause right after the next completed request
+        let completed = |simulator: &Simulator<_, EchoClient, _>| {
+            simulator.clients()[0].client().completed_requests.len()
+        };
+        let before = completed(&simulator);
+        let stop = simulator.run_until(|simulator| completed(simulator) > before);
+        assert_eq!(stop, StopReason::Paused);
+        assert_eq!(completed(&simulator), before + 1);
+
+        assert!(simulator.run());
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+        simulator.run_until_time(Duration::from_millis(500));
+
+        for seed in 0..10 {
+            let mut fork = simulator.fork(seed);
+            let mut same_future = simulator.fork(seed);
+
+            assert!(fork.run());
+            assert!(same_future.run());
+            assert_eq!(fork.elapsed(), same_future.elapsed());
+        }
+
+        // the original simulation is not affected by its forks
+        assert!(simulator.elapsed() <= Duration::from_millis(500));
+        assert!(simulator.run());
+    }
+
+    #[test]
+    fn test_model_checker() {
+        let server = EchoServer {
+            id: NodeId::Node(0),
+            replied_requests: BTreeSet::new(),
+        };
+        let client = EchoClient::new(2, Duration::from_millis(100), true);
+        let config = ModelCheckerConfig {
+            max_depth: 10,
+            ..ModelCheckerConfig::default()
+        };
+
+        let checker = ModelChecker::new(
+            Instant::now(),
+            vec![server],
+            vec![client],
+            config,
+            EchoInvariantChecker {},
+        );
+        let result = checker.run();
+
+        // ticks that change nothing lead to known states, so the state space
+        // is finite
+        assert!(result.states_explored > 10);
+        assert!(result.exhaustive);
+    }
+
+    #[test]
+    fn test_pct_scheduling() {
+        for seed in 0..10 {
+            let config = Configuration {
+                seed,
+                scheduling: SchedulingStrategy::Pct(PctConfiguration {
+                    depth: 3,
+                    max_steps: 100,
+                }),
+                ..echo_config(NetworkConfig::default())
+            };
+            let mut simulator = echo_simulator(config, 10, true);
+
+            assert!(simulator.run());
+        }
+    }
+
+    // Echo server and client that report duplicate requests and retries as
+    // coverage points.
+    #[derive(Debug, Clone)]
+    struct CoveredServer(EchoServer);
+
+    impl DeterministicNode for CoveredServer {
+        type Message = EchoMessage;
+
+        fn id(&self) -> NodeId {
+            self.0.id()
+        }
+
+        fn tick(&mut self, now: Instant) -> Vec<Self::Message> {
+            self.0.tick(now)
+        }
+
+        fn process_message(&mut self, msg: Self::Message, now: Instant) -> Vec<Self::Message> {
+            if let EchoMessage::Request { id, .. } = &msg {
+                if self.0.replied_requests.contains(id) {
+                    cover("duplicate request");
+                }
+            }
+            self.0.process_message(msg, now)
+        }
+
+        fn recover(&mut self, now: Instant, nonce: u64, replica_count: usize) {
+            self.0.recover(now, nonce, replica_count);
+        }
+
+        fn is_recovering(&self) -> bool {
+            self.0.is_recovering()
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct CoveredClient(EchoClient);
+
+    impl DeterministicClient for CoveredClient {
+        type Message = EchoMessage;
+
+        fn id(&self) -> NodeId {
+            self.0.id()
+        }
+
+        fn tick(&mut self, now: Instant) -> Vec<Self::Message> {
+            let current_request = self.0.current_request;
+            let messages = self.0.tick(now);
+            // new requests get the next id
+            let is_retry = messages.iter().any(
+                |msg| matches!(msg, EchoMessage::Request { id, .. } if *id == current_request),
+            );
+            if is_retry {
+                cover("retry");
+            }
+            messages
+        }
+
+        fn process_message(&mut self, msg: Self::Message, now: Instant) -> Vec<Self::Message> {
+            self.0.process_message(msg, now)
+        }
+
+        fn finished(&self) -> bool {
+            self.0.finished()
+        }
+
+        fn recover(&mut self, now: Instant, nonce: u64) {
+            self.0.recover(now, nonce);
+        }
+    }
+
+    #[test]
+    fn test_coverage_explorer() {
+        let base = echo_config(NetworkConfig::default());
+        let mut explorer = CoverageExplorer::new(base, 1);
+
+        let report = explorer.run(20, |config| {
+            let server = CoveredServer(EchoServer {
+                id: NodeId::Node(0),
+                replied_requests: BTreeSet::new(),
+            });
+            let client = CoveredClient(EchoClient::new(10, Duration::from_millis(200), true));
+            Simulator::new(Instant::now(), vec![server], vec![client], config, ()).run()
+        });
+
+        assert_eq!(report.runs, 20);
+        assert!(report.coverage.contains("retry"));
+        assert!(report.coverage.contains("duplicate request"));
+        assert!(!report.corpus.is_empty());
+    }
+
+    #[test]
+    fn test_swarm_sweep() {
+        let sweep = Sweep::new(echo_config(NetworkConfig::default()), 0..20).swarm();
+
+        // the derived configuration is reproducible from the seed
+        let config = sweep.config(3);
+        assert_eq!(config.seed, 3);
+        assert_eq!(format!("{:?}", config), format!("{:?}", sweep.config(3)));
+
+        let report = sweep.run(|config| echo_simulator(config, 10, true).run());
+        assert_eq!(report.runs, 20);
+        assert!(report.failures.is_empty());
+    }
+
+    #[test]
+    fn test_sequence_diagram() {
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+        simulator.record_trace();
+        assert!(simulator.run());
+
+        let trace = simulator.trace().unwrap();
+        let metrics = simulator.network_metrics();
+        let network_drops = trace
+            .entries()
+            .iter()
+            .filter(|entry| {
+                matches!(entry.event, TraceEvent::Drop { reason, .. } if reason != DropReason::NodeDown)
+            })
+            .count();
+        assert_eq!(
+            network_drops,
+            metrics.link_failure_drops + metrics.partition_drops + metrics.overflow_drops
+        );
+
+        let mermaid = trace.sequence_diagram(DiagramFormat::Mermaid, None);
+        assert!(mermaid.starts_with("sequenceDiagram\n"));
+        assert!(mermaid.contains("participant N0 as Node(0)"));
+        assert!(mermaid.contains("C0->>N0: "));
+        assert!(mermaid.contains("N0->>C0: "));
+
+        let plantuml = trace.sequence_diagram(DiagramFormat::PlantUml, None);
+        assert!(plantuml.starts_with("@startuml\n"));
+        assert!(plantuml.ends_with("@enduml\n"));
+        assert!(plantuml.contains("C0 -> N0 : "));
+
+        // nothing happens before the first tick
+        let empty =
+            trace.sequence_diagram(DiagramFormat::Mermaid, Some(Duration::ZERO..Duration::ZERO));
+        assert_eq!(empty, "sequenceDiagram\n");
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let config = Configuration {
+            client_failure_config: Some(FailureConfiguration {
+                mean_time_between_failures: Some(Duration::from_millis(500)),
+                mean_time_to_recover: Duration::from_millis(200),
+            }),
+            ..echo_config(NetworkConfig::default())
+        };
+        let mut simulator = echo_simulator(config, 30, true);
+        simulator.record_trace();
+        assert!(simulator.run());
+
+        let trace = simulator.trace().unwrap();
+        let export = trace.chrome_trace();
+        assert!(export.starts_with("{\"displayTimeUnit\":\"ms\",\"traceEvents\":["));
+        assert!(export.contains("\"args\":{\"name\":\"Client(0)\"}"));
+        assert!(export.contains("\"name\":\"down\",\"ph\":\"X\""));
+        assert!(export.contains("\"name\":\"partition\",\"ph\":\"X\""));
+        assert!(export.contains("\"name\":\"invariant check\",\"ph\":\"i\",\"s\":\"g\""));
+        // message contents are escaped
+        assert!(export.contains("\\\"echo_1\\\""));
+
+        let deliveries = trace
+            .entries()
+            .iter()
+            .filter(|entry| matches!(entry.event, TraceEvent::Deliver { .. }))
+            .count();
+        assert_eq!(export.matches("\"ph\":\"f\"").count(), deliveries);
+        assert_eq!(export.matches("\"ph\":\"s\"").count(), deliveries);
+    }
+
+    #[derive(Default)]
+    struct CountingObserver {
+        sends: usize,
+        deliveries: usize,
+        duplicates: usize,
+        drops: usize,
+        partitions: usize,
+        ticks: usize,
+    }
+
+    impl SimulationObserver<EchoMessage> for CountingObserver {
+        fn on_send(&mut self, _time: Duration, _message_id: usize, _message: &EchoMessage) {
+            self.sends += 1;
+        }
+
+        fn on_deliver(&mut self, _time: Duration, _message_id: usize, _message: &EchoMessage) {
+            self.deliveries += 1;
+        }
+
+        fn on_drop(&mut self, _time: Duration, _message: &EchoMessage, _reason: DropReason) {
+            self.drops += 1;
+        }
+
+        fn on_duplicate(&mut self, _time: Duration, _message: &EchoMessage) {
+            self.duplicates += 1;
+        }
+
+        fn on_partition_start(&mut self, _time: Duration, partitioned: &[NodeId]) {
+            assert!(!partitioned.is_empty());
+            self.partitions += 1;
+        }
+
+        fn on_tick(&mut self, _time: Duration) {
+            self.ticks += 1;
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut simulator = echo_simulator(echo_config(NetworkConfig::default()), 10, true);
+        let observer = Rc::new(RefCell::new(CountingObserver::default()));
+        simulator.add_observer(observer.clone());
+        assert!(simulator.run());
+
+        let observer = observer.borrow();
+        assert!(observer.duplicates > 0);
+        assert!(observer.drops > 0);
+        assert!(observer.partitions > 0);
+        assert!(observer.ticks > 0);
+        // every message and duplicate is either delivered, dropped or still in
+        // flight at the end
+        let in_flight = simulator
+            .pending_events()
+            .filter(|(_, event)| matches!(event, Event::Message(_)))
+            .count();
+        assert_eq!(
+            observer.sends + observer.duplicates,
+            observer.deliveries + observer.drops + in_flight
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut simulator = echo_simulator(echo_config(fault_free_network()), 10, true);
+        simulator.record_trace();
+        simulator.add_rule(
+            Rule::new(
+                |_, _, msg, _| matches!(msg, EchoMessage::Request { .. }),
+                RuleAction::Drop,
+            )
+            .skip(2)
+            .limit(1),
+        );
+        simulator.add_rule(Rule::new(
+            |_, to, msg, _| {
+                to == NodeId::Client(0) && matches!(msg, EchoMessage::Response { id: 5, .. })
+            },
+            RuleAction::Delay(Duration::from_secs(1)),
+        ));
+        simulator.add_rule(Rule::new(|_, _, _, _| true, RuleAction::Duplicate(2)).limit(1));
+
+        assert!(simulator.run());
+        assert_eq!(simulator.network_metrics().rule_drops, 1);
+        assert!(simulator.elapsed() >= Duration::from_secs(1));
+
+        let entries = simulator.trace().unwrap().entries();
+        let drops = entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                TraceEvent::Drop { message, reason } => Some((message, *reason)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(drops.len(), 1);
+        assert!(matches!(
+            drops[0],
+            (EchoMessage::Request { .. }, DropReason::Rule)
+        ));
+        let duplicates = entries
+            .iter()
+            .filter(|entry| matches!(entry.event, TraceEvent::Duplicate { .. }))
+            .count();
+        assert_eq!(duplicates, 2);
+    }
+
+    #[test]
+    fn test_delayed_messages_occupy_link_queue() {
+        let network_config = NetworkConfig {
+            link_queue_capacity: Some(1),
+            ..fault_free_network()
+        };
+        let mut simulator = echo_simulator(echo_config(network_config), 3, true);
+        simulator.add_rule(
+            Rule::new(
+                |_, _, msg, _| matches!(msg, EchoMessage::Request { .. }),
+                RuleAction::HoldUntil(Duration::from_secs(1)),
+            )
+            .limit(1),
+        );
+
+        assert!(simulator.run());
+        // the retries of the first request find the queue still occupied by
+        // the held request
+        assert!(simulator.network_metrics().overflow_drops > 0);
+    }
+
+    #[test]
+    fn test_link_queue_per_direction() {
+        let network_config = NetworkConfig {
+            link_queue_capacity: Some(1),
+            ..fault_free_network()
+        };
+        let mut simulator = echo_simulator(echo_config(network_config), 5, true);
+        simulator.record_trace();
+        // retries are sent while the response is still in flight
+        simulator.add_rule(Rule::new(
+            |_, _, msg, _| matches!(msg, EchoMessage::Response { .. }),
+            RuleAction::Delay(Duration::from_millis(300)),
+        ));
+
+        assert!(simulator.run());
+        let dropped_requests = simulator
+            .trace()
+            .unwrap()
+            .entries()
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.event,
+                    TraceEvent::Drop {
+                        message: EchoMessage::Request { .. },
+                        reason: DropReason::Overflow,
+                    }
+                )
+            })
+            .count();
+        assert!(simulator.network_metrics().overflow_drops > 0);
+        assert_eq!(dropped_requests, 0);
+    }
+
+    #[test]
+    fn test_fault_script() {
+        let mut simulator = echo_simulator(echo_config(fault_free_network()), 20, true);
+        simulator.record_trace();
+        simulator.add_fault_script(
+            FaultScript::new()
+                .rule_at(
+                    Duration::from_millis(500),
+                    Rule::new(|_, _, _, _| true, RuleAction::Drop).limit(2),
+                )
+                .rule_at(
+                    Duration::ZERO,
+                    Rule::new(|_, _, _, _| false, RuleAction::Drop),
+                ),
+        );
+
+        assert!(simulator.run());
+        assert_eq!(simulator.network_metrics().rule_drops, 2);
+        let drop_times = simulator
+            .trace()
+            .unwrap()
+            .entries()
+            .iter()
+            .filter(|entry| matches!(entry.event, TraceEvent::Drop { .. }))
+            .map(|entry| entry.time)
+            .collect::<Vec<_>>();
+        assert_eq!(drop_times.len(), 2);
+        assert!(drop_times[0] >= Duration::from_millis(500));
+    }
 }
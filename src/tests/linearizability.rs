@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        check_linearizability, node::NodeId, History, KvMap, KvOp, KvRet, Queue, QueueOp, QueueRet,
+        Register, RegisterOp, RegisterRet,
+    };
+
+    fn at(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_concurrent_register_operations() {
+        let start = Instant::now();
+        let mut history = History::new();
+
+        // the read overlaps with the write, so it may see either value
+        let write = history.invoke(NodeId::Client(0), RegisterOp::Write(1), at(start, 0));
+        let read = history.invoke(NodeId::Client(1), RegisterOp::Read, at(start, 5));
+        history.complete(read, RegisterRet::Value(None), at(start, 10));
+        history.complete(write, RegisterRet::Written, at(start, 20));
+        let read = history.invoke(NodeId::Client(1), RegisterOp::Read, at(start, 30));
+        history.complete(read, RegisterRet::Value(Some(1)), at(start, 40));
+
+        assert!(check_linearizability(&history, Register::default()).is_ok());
+    }
+
+    #[test]
+    fn test_stale_read() {
+        let start = Instant::now();
+        let mut history = History::new();
+
+        let write = history.invoke(NodeId::Client(0), RegisterOp::Write(1), at(start, 0));
+        history.complete(write, RegisterRet::Written, at(start, 10));
+        let write = history.invoke(NodeId::Client(0), RegisterOp::Write(2), at(start, 20));
+        history.complete(write, RegisterRet::Written, at(start, 30));
+        let read = history.invoke(NodeId::Client(1), RegisterOp::Read, at(start, 25));
+        history.complete(read, RegisterRet::Value(Some(2)), at(start, 35));
+        // reads the overwritten value after the second write completed
+        let read = history.invoke(NodeId::Client(1), RegisterOp::Read, at(start, 40));
+        history.complete(read, RegisterRet::Value(Some(1)), at(start, 50));
+
+        let violation = check_linearizability(&history, Register::default()).unwrap_err();
+        assert_eq!(violation.operations.len(), 3);
+        assert_eq!(violation.operations[0].op, RegisterOp::Write(1));
+        assert_eq!(violation.operations[1].op, RegisterOp::Write(2));
+        assert_eq!(violation.operations[2].op, RegisterOp::Read);
+        assert_eq!(violation.culprit, 2);
+    }
+
+    #[test]
+    fn test_pending_and_failed_operations() {
+        let start = Instant::now();
+        let mut history = History::new();
+
+        // a pending write may take effect at any point after its invocation
+        history.invoke(NodeId::Client(0), KvOp::Put("a", 1), at(start, 0));
+        let failed = history.invoke(NodeId::Client(0), KvOp::Put("a", 2), at(start, 10));
+        history.fail(failed, at(start, 20));
+        let get = history.invoke(NodeId::Client(1), KvOp::Get("a"), at(start, 30));
+        history.complete(get, KvRet::Value(Some(1)), at(start, 40));
+
+        assert!(check_linearizability(&history, KvMap::default()).is_ok());
+
+        // the failed write must not be visible
+        let get = history.invoke(NodeId::Client(1), KvOp::Get("a"), at(start, 50));
+        history.complete(get, KvRet::Value(Some(2)), at(start, 60));
+
+        assert!(check_linearizability(&history, KvMap::default()).is_err());
+    }
+
+    #[test]
+    fn test_merged_queue_histories() {
+        let start = Instant::now();
+        let mut producer = History::new();
+        let mut consumer = History::new();
+
+        let enqueue = producer.invoke(NodeId::Client(0), QueueOp::Enqueue(1), at(start, 0));
+        producer.complete(enqueue, QueueRet::Enqueued, at(start, 10));
+        let enqueue = producer.invoke(NodeId::Client(0), QueueOp::Enqueue(2), at(start, 20));
+        producer.complete(enqueue, QueueRet::Enqueued, at(start, 30));
+        let dequeue = consumer.invoke(NodeId::Client(1), QueueOp::Dequeue, at(start, 40));
+        consumer.complete(dequeue, QueueRet::Value(Some(2)), at(start, 50));
+
+        let history = History::merged([&producer, &consumer]);
+        let violation = check_linearizability(&history, Queue::default()).unwrap_err();
+        assert!(violation.to_string().contains("Dequeue"));
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        node::NodeId, Configuration, DeterministicClient, DeterministicNode, MembershipChange,
+        ProtocolMessage, Simulator,
+    };
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    struct Ping {
+        from: NodeId,
+        to: NodeId,
+    }
+
+    impl ProtocolMessage for Ping {
+        fn source(&self) -> NodeId {
+            self.from
+        }
+
+        fn destination(&self) -> NodeId {
+            self.to
+        }
+    }
+
+    // Node(0) pings Node(1) on every tick and reconfigures the cluster on
+    // given ticks: it spawns Node(1) and later removes it again, in between it
+    // requests changes that cannot be applied.
+    #[derive(Debug, Clone)]
+    struct Member {
+        id: NodeId,
+        ticks: usize,
+        changes: Vec<(usize, MembershipChange)>,
+        requested: Vec<MembershipChange>,
+        received_at: Vec<Instant>,
+        decommissioned_at: Option<Instant>,
+        pings_after_decommission: usize,
+    }
+
+    impl Member {
+        fn new(id: NodeId, changes: Vec<(usize, MembershipChange)>) -> Self {
+            Member {
+                id,
+                ticks: 0,
+                changes,
+                requested: vec![],
+                received_at: vec![],
+                decommissioned_at: None,
+                pings_after_decommission: 0,
+            }
+        }
+    }
+
+    impl DeterministicNode for Member {
+        type Message = Ping;
+
+        fn id(&self) -> NodeId {
+            self.id
+        }
+
+        fn tick(&mut self, now: Instant) -> Vec<Self::Message> {
+            self.ticks += 1;
+            for (tick, change) in &self.changes {
+                if *tick == self.ticks {
+                    self.requested.push(*change);
+                    if *change == MembershipChange::Decommission(NodeId::Node(1)) {
+                        self.decommissioned_at = Some(now);
+                    }
+                }
+            }
+            if self.id != NodeId::Node(0) {
+                return vec![];
+            }
+            if self.decommissioned_at.is_some() {
+                self.pings_after_decommission += 1;
+            }
+            vec![Ping {
+                from: self.id,
+                to: NodeId::Node(1),
+            }]
+        }
+
+        fn process_message(&mut self, _msg: Self::Message, now: Instant) -> Vec<Self::Message> {
+            self.received_at.push(now);
+            vec![]
+        }
+
+        fn recover(&mut self, _now: Instant, _nonce: u64, _replica_count: usize) {}
+
+        fn is_recovering(&self) -> bool {
+            false
+        }
+
+        fn membership_changes(&mut self) -> Vec<MembershipChange> {
+            std::mem::take(&mut self.requested)
+        }
+    }
+
+    // Finishes after the given number of ticks.
+    #[derive(Debug, Clone)]
+    struct Timer {
+        ticks: usize,
+    }
+
+    impl DeterministicClient for Timer {
+        type Message = Ping;
+
+        fn id(&self) -> NodeId {
+            NodeId::Client(0)
+        }
+
+        fn tick(&mut self, _now: Instant) -> Vec<Self::Message> {
+            self.ticks = self.ticks.saturating_sub(1);
+            vec![]
+        }
+
+        fn process_message(&mut self, _msg: Self::Message, _now: Instant) -> Vec<Self::Message> {
+            vec![]
+        }
+
+        fn finished(&self) -> bool {
+            self.ticks == 0
+        }
+    }
+
+    fn simulator(
+        changes: Vec<(usize, MembershipChange)>,
+        with_factory: bool,
+    ) -> Simulator<Member, Timer, ()> {
+        let mut builder = Simulator::builder()
+            .node(Member::new(NodeId::Node(0), changes))
+            .client(Timer { ticks: 30 })
+            .config(Configuration::reliable())
+            .invariant_checker(());
+        if with_factory {
+            builder = builder.node_factory(|id| Member::new(id, vec![]));
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_requested_membership_changes() {
+        let mut simulator = simulator(
+            vec![
+                (3, MembershipChange::Spawn),
+                (5, MembershipChange::Decommission(NodeId::Client(0))),
+                (6, MembershipChange::Decommission(NodeId::Node(7))),
+                (10, MembershipChange::Decommission(NodeId::Node(1))),
+            ],
+            true,
+        );
+        assert!(simulator.run());
+
+        let nodes = simulator.nodes();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes[0].is_up());
+        assert!(!nodes[1].is_up());
+
+        // the removed node receives nothing sent after it was removed
+        let coordinator = nodes[0].node();
+        let decommissioned_at = coordinator.decommissioned_at.unwrap();
+        assert!(coordinator.pings_after_decommission > 10);
+        let received_at = &nodes[1].node().received_at;
+        assert!(!received_at.is_empty());
+        assert!(received_at.iter().all(|time| *time <= decommissioned_at));
+        assert!(simulator.elapsed() > Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_spawn_without_factory() {
+        let mut simulator = simulator(vec![(3, MembershipChange::Spawn)], false);
+        assert!(simulator.run());
+        assert_eq!(simulator.nodes().len(), 1);
+    }
+}
@@ -1 +1,9 @@
+mod config;
+mod config_file;
 mod echo;
+mod linearizability;
+mod membership;
+mod model_checker;
+mod scheduler;
+mod transactions;
+mod workload;
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        panic::{self, AssertUnwindSafe},
+        time::Instant,
+    };
+
+    use crate::{
+        node::NodeId, Choice, Client, DeterministicClient, DeterministicNode, InvariantChecker,
+        ModelChecker, ModelCheckerConfig, Node, ProtocolMessage,
+    };
+
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    enum Message {
+        Write(u64),
+        Replicate(u64),
+        Ack(u64),
+    }
+
+    impl ProtocolMessage for Message {
+        fn source(&self) -> NodeId {
+            match self {
+                Message::Write(_) => NodeId::Client(0),
+                Message::Replicate(_) | Message::Ack(_) => NodeId::Node(0),
+            }
+        }
+
+        fn destination(&self) -> NodeId {
+            match self {
+                Message::Write(_) => NodeId::Node(0),
+                Message::Replicate(_) => NodeId::Node(1),
+                Message::Ack(_) => NodeId::Client(0),
+            }
+        }
+    }
+
+    // Node 0 is the primary and node 1 the backup. The primary acknowledges
+    // writes without waiting for the backup.
+    #[derive(Debug, Clone, Hash)]
+    struct Replica {
+        id: NodeId,
+        values: BTreeSet<u64>,
+    }
+
+    impl DeterministicNode for Replica {
+        type Message = Message;
+
+        fn id(&self) -> NodeId {
+            self.id
+        }
+
+        fn tick(&mut self, _now: Instant) -> Vec<Self::Message> {
+            vec![]
+        }
+
+        fn process_message(&mut self, msg: Self::Message, _now: Instant) -> Vec<Self::Message> {
+            match msg {
+                Message::Write(value) => {
+                    self.values.insert(value);
+                    vec![Message::Replicate(value), Message::Ack(value)]
+                }
+                Message::Replicate(value) => {
+                    self.values.insert(value);
+                    vec![]
+                }
+                Message::Ack(_) => vec![],
+            }
+        }
+
+        fn recover(&mut self, _now: Instant, _nonce: u64, _replica_count: usize) {}
+
+        fn is_recovering(&self) -> bool {
+            false
+        }
+    }
+
+    #[derive(Debug, Clone, Hash)]
+    struct Writer {
+        sent: bool,
+        acknowledged: BTreeSet<u64>,
+    }
+
+    impl DeterministicClient for Writer {
+        type Message = Message;
+
+        fn id(&self) -> NodeId {
+            NodeId::Client(0)
+        }
+
+        fn tick(&mut self, _now: Instant) -> Vec<Self::Message> {
+            if self.sent {
+                return vec![];
+            }
+            self.sent = true;
+            vec![Message::Write(1)]
+        }
+
+        fn process_message(&mut self, msg: Self::Message, _now: Instant) -> Vec<Self::Message> {
+            if let Message::Ack(value) = msg {
+                self.acknowledged.insert(value);
+            }
+            vec![]
+        }
+
+        fn finished(&self) -> bool {
+            !self.acknowledged.is_empty()
+        }
+
+        fn recover(&mut self, _now: Instant, _nonce: u64) {}
+    }
+
+    // Every acknowledged write is stored on the backup.
+    struct ReplicationChecker;
+
+    impl InvariantChecker<Replica, Writer> for ReplicationChecker {
+        fn check_invariants(
+            &self,
+            _seed: u64,
+            nodes: &[Node<Replica>],
+            clients: &[Client<Writer>],
+        ) {
+            for value in &clients[0].client().acknowledged {
+                assert!(
+                    nodes[1].node().values.contains(value),
+                    "write {} acknowledged but not replicated",
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_model_checker_finds_violation() {
+        let nodes = (0..2)
+            .map(|index| Replica {
+                id: NodeId::Node(index),
+                values: BTreeSet::new(),
+            })
+            .collect();
+        let writer = Writer {
+            sent: false,
+            acknowledged: BTreeSet::new(),
+        };
+        let checker = ModelChecker::new(
+            Instant::now(),
+            nodes,
+            vec![writer],
+            ModelCheckerConfig::default(),
+            ReplicationChecker,
+        );
+
+        let violation = panic::catch_unwind(AssertUnwindSafe(|| checker.run())).unwrap_err();
+        let message = violation.downcast_ref::<String>().unwrap();
+
+        // the shortest trace delivers the acknowledgement before the backup
+        // got the write
+        let choices = [
+            Choice::Tick,
+            Choice::Deliver(Message::Write(1)),
+            Choice::Deliver(Message::Ack(1)),
+        ];
+        assert!(
+            message.contains(&format!("{:?}", choices)),
+            "unexpected report: {}",
+            message
+        );
+        assert!(message.contains("write 1 acknowledged but not replicated"));
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::{
+        node::NodeId, scheduler::EventQueue, Event, PctConfiguration, ProtocolMessage,
+        SchedulingStrategy, SimulationMessage,
+    };
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    struct Message {
+        to: NodeId,
+    }
+
+    impl ProtocolMessage for Message {
+        fn source(&self) -> NodeId {
+            NodeId::Client(0)
+        }
+
+        fn destination(&self) -> NodeId {
+            self.to
+        }
+    }
+
+    // Returns the receivers in the order the queue delivers four messages
+    // that are due at the same time, alternately to two nodes.
+    fn delivery_order(strategy: &SchedulingStrategy, seed: u64) -> Vec<NodeId> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut events = EventQueue::new(strategy, &mut rng);
+        let now = Instant::now();
+        for (index, to) in [NodeId::Node(0), NodeId::Node(1)]
+            .into_iter()
+            .cycle()
+            .take(4)
+            .enumerate()
+        {
+            let message = SimulationMessage::new(Message { to }, index, index as u64);
+            events.push(now, Event::Message(message), &mut rng);
+        }
+
+        let mut order = vec![];
+        while let Some((event_time, _)) = events.peek() {
+            let event_time = event_time.clone();
+            let Some(Event::Message(message)) = events.remove(&event_time) else {
+                unreachable!();
+            };
+            order.push(message.message().destination());
+        }
+        order
+    }
+
+    #[test]
+    fn test_pct_change_point() {
+        for seed in 0..10 {
+            let fifo = delivery_order(&SchedulingStrategy::TimeOrdered, seed);
+            assert_eq!(
+                fifo,
+                [
+                    NodeId::Node(0),
+                    NodeId::Node(1),
+                    NodeId::Node(0),
+                    NodeId::Node(1)
+                ]
+            );
+
+            // the only change point is the first step: the first receiver
+            // drops below the other one after its first message
+            let pct = delivery_order(
+                &SchedulingStrategy::Pct(PctConfiguration {
+                    depth: 2,
+                    max_steps: 1,
+                }),
+                seed,
+            );
+            assert_ne!(pct, fifo);
+            assert_eq!(pct[0], pct[3]);
+            assert_eq!(pct[1], pct[2]);
+            assert_ne!(pct[0], pct[1]);
+        }
+    }
+}
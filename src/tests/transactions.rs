@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        check_transactions, node::NodeId, AnomalyKind, Dependency, IsolationLevel, MicroOp,
+        Transaction, TransactionHistory,
+    };
+
+    // Records committed transactions, one after the other.
+    fn history(
+        transactions: Vec<Transaction<&'static str, u64>>,
+    ) -> TransactionHistory<&'static str, u64> {
+        let start = Instant::now();
+        let mut history = TransactionHistory::new();
+        for (i, transaction) in transactions.into_iter().enumerate() {
+            let time = start + Duration::from_millis(10 * i as u64);
+            let id = history.invoke(NodeId::Client(i), transaction.clone(), time);
+            history.complete(id, transaction, time + Duration::from_millis(5));
+        }
+        history
+    }
+
+    fn anomaly_kinds(
+        history: &TransactionHistory<&'static str, u64>,
+        level: IsolationLevel,
+    ) -> Vec<AnomalyKind> {
+        match check_transactions(history, level) {
+            Ok(()) => vec![],
+            Err(anomalies) => anomalies.iter().map(|anomaly| anomaly.kind).collect(),
+        }
+    }
+
+    #[test]
+    fn test_serializable_history() {
+        let history = history(vec![
+            vec![MicroOp::Append("x", 1), MicroOp::Append("y", 1)],
+            vec![MicroOp::ReadList("x", vec![1]), MicroOp::Append("x", 2)],
+            vec![
+                MicroOp::ReadList("x", vec![1, 2]),
+                MicroOp::ReadList("y", vec![1]),
+            ],
+        ]);
+
+        assert!(check_transactions(&history, IsolationLevel::Serializable).is_ok());
+    }
+
+    #[test]
+    fn test_g0_write_cycle() {
+        let history = history(vec![
+            vec![MicroOp::Append("x", 1), MicroOp::Append("y", 2)],
+            vec![MicroOp::Append("x", 2), MicroOp::Append("y", 1)],
+            vec![
+                MicroOp::ReadList("x", vec![1, 2]),
+                MicroOp::ReadList("y", vec![1, 2]),
+            ],
+        ]);
+
+        assert_eq!(
+            anomaly_kinds(&history, IsolationLevel::ReadCommitted),
+            vec![AnomalyKind::G0]
+        );
+    }
+
+    #[test]
+    fn test_g1c_cyclic_information_flow() {
+        let history = history(vec![
+            vec![MicroOp::Write("x", 1), MicroOp::Read("y", Some(2))],
+            vec![MicroOp::Write("y", 2), MicroOp::Read("x", Some(1))],
+        ]);
+
+        let anomalies = check_transactions(&history, IsolationLevel::ReadCommitted).unwrap_err();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::G1c);
+        assert!(anomalies[0]
+            .cycle
+            .iter()
+            .all(|step| step.dependency == Dependency::WriteRead));
+    }
+
+    #[test]
+    fn test_g_single_read_skew() {
+        let history = history(vec![
+            vec![MicroOp::Append("x", 1), MicroOp::Append("y", 1)],
+            // sees the append to y but not the one to x
+            vec![
+                MicroOp::ReadList("x", vec![]),
+                MicroOp::ReadList("y", vec![1]),
+            ],
+            vec![
+                MicroOp::ReadList("x", vec![1]),
+                MicroOp::ReadList("y", vec![1]),
+            ],
+        ]);
+
+        assert!(check_transactions(&history, IsolationLevel::ReadCommitted).is_ok());
+        let anomalies =
+            check_transactions(&history, IsolationLevel::SnapshotIsolation).unwrap_err();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::GSingle);
+        assert_eq!(anomalies[0].cycle.len(), 2);
+        assert!(anomalies[0].to_string().contains("ReadWrite"));
+    }
+
+    #[test]
+    fn test_g2_write_skew() {
+        let history = history(vec![
+            vec![
+                MicroOp::Read("x", None),
+                MicroOp::Read("y", None),
+                MicroOp::Write("x", 1),
+            ],
+            vec![
+                MicroOp::Read("x", None),
+                MicroOp::Read("y", None),
+                MicroOp::Write("y", 2),
+            ],
+        ]);
+
+        // write skew is allowed under snapshot isolation
+        assert!(check_transactions(&history, IsolationLevel::SnapshotIsolation).is_ok());
+        assert_eq!(
+            anomaly_kinds(&history, IsolationLevel::Serializable),
+            vec![AnomalyKind::G2]
+        );
+    }
+}
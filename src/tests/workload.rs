@@ -0,0 +1,333 @@
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, time::Duration, time::Instant};
+
+    use rand::Rng;
+
+    use crate::{
+        check_linearizability, node::NodeId, Arrivals, Backoff, Configuration, DeterministicNode,
+        Event, FailureConfiguration, History, LatencyHistogram, OperationMix, ProtocolMessage,
+        Register, RegisterOp, RegisterRet, Reply, Simulator, Workload, WorkloadClient,
+        WorkloadConfig,
+    };
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    enum RegisterMessage {
+        Request {
+            client: NodeId,
+            request_id: u64,
+            op: RegisterOp<u64>,
+        },
+        Response {
+            client: NodeId,
+            request_id: u64,
+            ret: RegisterRet<u64>,
+        },
+    }
+
+    impl ProtocolMessage for RegisterMessage {
+        fn source(&self) -> NodeId {
+            match self {
+                RegisterMessage::Request { client, .. } => *client,
+                RegisterMessage::Response { .. } => NodeId::Node(0),
+            }
+        }
+
+        fn destination(&self) -> NodeId {
+            match self {
+                RegisterMessage::Request { .. } => NodeId::Node(0),
+                RegisterMessage::Response { client, .. } => *client,
+            }
+        }
+    }
+
+    // Applies each request once and answers retries with the original result.
+    #[derive(Debug, Clone)]
+    struct RegisterServer {
+        value: Option<u64>,
+        results: BTreeMap<(NodeId, u64), RegisterRet<u64>>,
+    }
+
+    impl DeterministicNode for RegisterServer {
+        type Message = RegisterMessage;
+
+        fn id(&self) -> NodeId {
+            NodeId::Node(0)
+        }
+
+        fn tick(&mut self, _now: Instant) -> Vec<Self::Message> {
+            vec![]
+        }
+
+        fn process_message(&mut self, msg: Self::Message, _now: Instant) -> Vec<Self::Message> {
+            let RegisterMessage::Request {
+                client,
+                request_id,
+                op,
+            } = msg
+            else {
+                return vec![];
+            };
+            let value = &mut self.value;
+            let ret = self
+                .results
+                .entry((client, request_id))
+                .or_insert_with(|| match op {
+                    RegisterOp::Read => RegisterRet::Value(*value),
+                    RegisterOp::Write(new) => {
+                        *value = Some(new);
+                        RegisterRet::Written
+                    }
+                    RegisterOp::Cas { expected, new } => {
+                        let swapped = *value == Some(expected);
+                        if swapped {
+                            *value = Some(new);
+                        }
+                        RegisterRet::Cas(swapped)
+                    }
+                })
+                .clone();
+            vec![RegisterMessage::Response {
+                client,
+                request_id,
+                ret,
+            }]
+        }
+
+        fn recover(&mut self, _now: Instant, _nonce: u64, _replica_count: usize) {}
+
+        fn is_recovering(&self) -> bool {
+            false
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct RegisterWorkload;
+
+    impl Workload for RegisterWorkload {
+        type Message = RegisterMessage;
+        type Op = RegisterOp<u64>;
+        type Ret = RegisterRet<u64>;
+
+        fn encode(
+            &self,
+            client: NodeId,
+            request_id: u64,
+            _attempt: u32,
+            op: &Self::Op,
+        ) -> Self::Message {
+            RegisterMessage::Request {
+                client,
+                request_id,
+                op: op.clone(),
+            }
+        }
+
+        fn decode(&self, message: &Self::Message) -> Option<(u64, Reply<Self::Ret>)> {
+            match message {
+                RegisterMessage::Response {
+                    request_id, ret, ..
+                } => Some((*request_id, Reply::Ok(ret.clone()))),
+                RegisterMessage::Request { .. } => None,
+            }
+        }
+    }
+
+    fn register_mix() -> OperationMix<RegisterOp<u64>> {
+        OperationMix::new()
+            .with(1, |_| RegisterOp::Read)
+            .with(1, |rng| RegisterOp::Write(rng.gen_range(0..5)))
+            .with(1, |rng| RegisterOp::Cas {
+                expected: rng.gen_range(0..5),
+                new: rng.gen_range(0..5),
+            })
+    }
+
+    fn run_workload(
+        config: Configuration,
+        clients: usize,
+        workload_config: WorkloadConfig,
+    ) -> Simulator<RegisterServer, WorkloadClient<RegisterWorkload>, ()> {
+        let mut simulator = Simulator::builder()
+            .node(RegisterServer {
+                value: None,
+                results: BTreeMap::new(),
+            })
+            .clients((0..clients).map(|index| {
+                WorkloadClient::new(
+                    NodeId::Client(index),
+                    RegisterWorkload,
+                    register_mix(),
+                    workload_config.clone(),
+                )
+            }))
+            .config(config)
+            .invariant_checker(())
+            .build()
+            .unwrap();
+
+        assert!(simulator.run());
+        simulator
+    }
+
+    #[test]
+    fn test_closed_loop_workload() {
+        let simulator = run_workload(
+            Configuration {
+                max_sim_time: Duration::from_secs(120),
+                ..Configuration::flaky()
+            },
+            3,
+            WorkloadConfig {
+                arrivals: Arrivals::ClosedLoop { concurrency: 2 },
+                total_operations: 30,
+                ..WorkloadConfig::default()
+            },
+        );
+        let clients = simulator.clients();
+
+        let history = History::merged(clients.iter().map(|client| client.client().history()));
+        assert_eq!(history.operations().len(), 90);
+        assert!(check_linearizability(&history, Register::default()).is_ok());
+        assert!(clients.iter().any(|client| client.client().retries() > 0));
+        for client in clients {
+            let client = client.client();
+            assert_eq!(client.latencies().count() as u64 + client.abandoned(), 30);
+        }
+    }
+
+    #[test]
+    fn test_open_loop_workload() {
+        let workload_config = WorkloadConfig {
+            arrivals: Arrivals::OpenLoop {
+                mean_interarrival_time: Duration::from_millis(20),
+            },
+            total_operations: 100,
+            ..WorkloadConfig::default()
+        };
+        let simulator = run_workload(Configuration::reliable(), 1, workload_config.clone());
+
+        let client = simulator.clients()[0].client();
+        assert_eq!(client.latencies().count(), 100);
+        assert_eq!(client.retries(), 0);
+        // requests and replies take at most 10ms each
+        assert!(client.latencies().max().unwrap() <= Duration::from_millis(20));
+        // operations overlap since they arrive faster than ticks
+        let invocations = client
+            .history()
+            .operations()
+            .iter()
+            .map(|operation| operation.invoked_at)
+            .collect::<Vec<_>>();
+        assert!(invocations.windows(2).any(|pair| pair[0] == pair[1]));
+
+        let rerun = run_workload(Configuration::reliable(), 1, workload_config);
+        assert_eq!(client.latencies(), rerun.clients()[0].client().latencies());
+    }
+
+    #[test]
+    fn test_statistics_with_crashes() {
+        let simulator = run_workload(
+            Configuration {
+                max_sim_time: Duration::from_secs(300),
+                client_failure_config: Some(FailureConfiguration {
+                    mean_time_between_failures: Some(Duration::from_secs(2)),
+                    mean_time_to_recover: Duration::from_millis(500),
+                }),
+                ..Configuration::crash_heavy()
+            },
+            3,
+            WorkloadConfig {
+                arrivals: Arrivals::ClosedLoop { concurrency: 2 },
+                total_operations: 50,
+                max_retries: 2,
+                ..WorkloadConfig::default()
+            },
+        );
+        let statistics = simulator.statistics();
+
+        let mut latencies = LatencyHistogram::new();
+        let mut abandoned = 0;
+        for client in simulator.clients() {
+            latencies.merge(client.client().latencies());
+            abandoned += client.client().abandoned();
+        }
+        assert!(abandoned > 0);
+        assert_eq!(statistics.request_latencies(), &latencies);
+        // abandoned requests and messages to crashed nodes are not kept
+        assert_eq!(statistics.requests_in_progress(), 0);
+        let pending_messages = simulator
+            .pending_events()
+            .filter(|(_, event)| matches!(event, Event::Message(_)))
+            .count();
+        assert_eq!(statistics.messages_in_flight(), pending_messages);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(400));
+        assert_eq!(backoff.delay(10), Duration::from_secs(2));
+
+        let negative = Backoff {
+            multiplier: -2.0,
+            ..Backoff::default()
+        };
+        assert_eq!(negative.delay(1), Duration::ZERO);
+        let overflowing = Backoff {
+            multiplier: f64::MAX,
+            ..Backoff::default()
+        };
+        assert_eq!(overflowing.delay(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.p50(), None);
+        for millis in (1..=100).rev() {
+            histogram.record(Duration::from_millis(millis));
+        }
+        histogram.record(Duration::from_millis(100));
+
+        assert_eq!(histogram.count(), 101);
+        assert_eq!(histogram.min(), Some(Duration::from_millis(1)));
+        assert_eq!(histogram.p50(), Some(Duration::from_millis(51)));
+        assert_eq!(histogram.p99(), Some(Duration::from_millis(100)));
+        assert_eq!(histogram.percentile(0.0), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let workload_config = WorkloadConfig {
+            arrivals: Arrivals::ClosedLoop { concurrency: 2 },
+            total_operations: 50,
+            ..WorkloadConfig::default()
+        };
+        let simulator = run_workload(Configuration::reliable(), 2, workload_config.clone());
+        let statistics = simulator.statistics();
+
+        let mut latencies = LatencyHistogram::new();
+        for client in simulator.clients() {
+            latencies.merge(client.client().latencies());
+        }
+        assert_eq!(statistics.request_latencies(), &latencies);
+        assert_eq!(statistics.request_latencies().count(), 100);
+        assert!(statistics.request_throughput() > 0.0);
+
+        // every request is answered exactly once on a reliable network
+        let requests = statistics.link_messages()[&(NodeId::Client(0), NodeId::Node(0))];
+        assert_eq!(requests, 50);
+        assert_eq!(statistics.messages_sent(), 200);
+        assert_eq!(statistics.delivery_latencies().count(), 200);
+        assert!(statistics.delivery_latencies().p99() <= statistics.request_latencies().p99());
+
+        let rerun = run_workload(Configuration::reliable(), 2, workload_config);
+        assert_eq!(rerun.statistics().to_string(), statistics.to_string());
+        assert_eq!(
+            rerun.statistics().delivery_latencies(),
+            statistics.delivery_latencies()
+        );
+    }
+}
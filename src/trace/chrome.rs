@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    time::Duration,
+};
+
+use crate::{node::NodeId, ProtocolMessage};
+
+use super::{LinkStatus, Trace, TraceEvent};
+
+// Nodes and clients are threads of one process, links and partitions of
+// another one.
+const NODES_PID: usize = 1;
+const NETWORK_PID: usize = 2;
+const PARTITION_TID: usize = 0;
+
+impl<M: ProtocolMessage> Trace<M> {
+    /// Exports the trace in the Chrome Trace Event format, which can be opened
+    /// with Perfetto (<https://ui.perfetto.dev>) or `chrome://tracing`.
+    ///
+    /// Every node and client gets its own track with spans for its downtime,
+    /// slices for sent and delivered messages connected by flow arrows and
+    /// instant markers for dropped and duplicated messages. Link failures and
+    /// partitions are shown as spans on network tracks and invariant checks as
+    /// global instant markers. Spans that are still open at the end of the
+    /// trace end with the last recorded event.
+    pub fn chrome_trace(&self) -> String {
+        let end = self
+            .entries
+            .last()
+            .map(|entry| entry.time)
+            .unwrap_or_default();
+        let mut export = ChromeTrace::default();
+
+        let nodes = self
+            .entries
+            .iter()
+            .flat_map(|entry| match &entry.event {
+                TraceEvent::Send { message, .. }
+                | TraceEvent::Deliver { message, .. }
+                | TraceEvent::Duplicate { message }
+                | TraceEvent::Drop { message, .. } => {
+                    vec![message.source(), message.destination()]
+                }
+                TraceEvent::NodeCrash(node) | TraceEvent::NodeRecover(node) => vec![*node],
+                TraceEvent::LinkChange { from, to, .. } => vec![*from, *to],
+                TraceEvent::PartitionStart(nodes) => nodes.clone(),
+                TraceEvent::PartitionEnd | TraceEvent::InvariantCheck => vec![],
+            })
+            .collect::<BTreeSet<_>>();
+        let tids = nodes
+            .iter()
+            .enumerate()
+            .map(|(tid, node)| (*node, tid + 1))
+            .collect::<HashMap<_, _>>();
+
+        export.metadata("process_name", NODES_PID, 0, "Nodes");
+        export.metadata("process_name", NETWORK_PID, 0, "Network");
+        export.metadata("thread_name", NETWORK_PID, PARTITION_TID, "Partitions");
+        for node in &nodes {
+            export.metadata("thread_name", NODES_PID, tids[node], &node.to_string());
+        }
+
+        let mut sends: HashMap<usize, (Duration, NodeId)> = HashMap::new();
+        // Ordered, so spans that are still open are exported deterministically.
+        let mut crashes: BTreeMap<NodeId, Duration> = BTreeMap::new();
+        let mut link_tids: HashMap<(NodeId, NodeId), usize> = HashMap::new();
+        let mut link_failures: BTreeMap<(NodeId, NodeId), (Duration, LinkStatus)> = BTreeMap::new();
+        let mut partition: Option<(Duration, Vec<NodeId>)> = None;
+        let mut flow_id = 0;
+
+        for entry in &self.entries {
+            let time = entry.time;
+            match &entry.event {
+                TraceEvent::Send {
+                    message_id,
+                    message,
+                } => {
+                    let from = message.source();
+                    sends.insert(*message_id, (time, from));
+                    export.slice(
+                        &format!("send m{}", message_id),
+                        NODES_PID,
+                        tids[&from],
+                        time,
+                        Duration::ZERO,
+                        &format!("{:?}", message),
+                    );
+                }
+                TraceEvent::Deliver {
+                    message_id,
+                    message,
+                } => {
+                    let to = message.destination();
+                    export.slice(
+                        &format!("deliver m{}", message_id),
+                        NODES_PID,
+                        tids[&to],
+                        time,
+                        Duration::ZERO,
+                        &format!("{:?}", message),
+                    );
+                    // Duplicates share the message id, so every delivery gets
+                    // its own flow.
+                    if let Some((sent, from)) = sends.get(message_id) {
+                        flow_id += 1;
+                        export.flow("s", flow_id, NODES_PID, tids[from], *sent);
+                        export.flow("f", flow_id, NODES_PID, tids[&to], time);
+                    }
+                }
+                TraceEvent::Duplicate { message } => {
+                    export.instant(
+                        "duplicate",
+                        Some((NODES_PID, tids[&message.source()])),
+                        time,
+                        &format!("{:?}", message),
+                    );
+                }
+                TraceEvent::Drop { message, reason } => {
+                    export.instant(
+                        &format!("drop ({})", reason),
+                        Some((NODES_PID, tids[&message.destination()])),
+                        time,
+                        &format!("{:?}", message),
+                    );
+                }
+                TraceEvent::NodeCrash(node) => {
+                    crashes.insert(*node, time);
+                }
+                TraceEvent::NodeRecover(node) => {
+                    if let Some(crashed) = crashes.remove(node) {
+                        export.slice("down", NODES_PID, tids[node], crashed, time - crashed, "");
+                    }
+                }
+                TraceEvent::LinkChange { from, to, status } => {
+                    let next_tid = link_tids.len() + 1;
+                    let tid = *link_tids.entry((*from, *to)).or_insert_with(|| {
+                        export.metadata(
+                            "thread_name",
+                            NETWORK_PID,
+                            next_tid,
+                            &format!("{} <-> {}", from, to),
+                        );
+                        next_tid
+                    });
+                    if let Some((failed, status)) = link_failures.remove(&(*from, *to)) {
+                        export.link_failure(tid, failed, time, status);
+                    }
+                    if *status != LinkStatus::Up {
+                        link_failures.insert((*from, *to), (time, *status));
+                    }
+                }
+                TraceEvent::PartitionStart(nodes) => {
+                    partition = Some((time, nodes.clone()));
+                }
+                TraceEvent::PartitionEnd => {
+                    if let Some((started, nodes)) = partition.take() {
+                        export.partition(started, time, &nodes);
+                    }
+                }
+                TraceEvent::InvariantCheck => {
+                    export.instant("invariant check", None, time, "");
+                }
+            }
+        }
+
+        for (node, crashed) in crashes {
+            export.slice("down", NODES_PID, tids[&node], crashed, end - crashed, "");
+        }
+        for (link, (failed, status)) in link_failures {
+            export.link_failure(link_tids[&link], failed, end, status);
+        }
+        if let Some((started, nodes)) = partition {
+            export.partition(started, end, &nodes);
+        }
+
+        format!(
+            "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\n{}\n]}}\n",
+            export.events.join(",\n")
+        )
+    }
+}
+
+#[derive(Default)]
+struct ChromeTrace {
+    events: Vec<String>,
+}
+
+impl ChromeTrace {
+    fn metadata(&mut self, kind: &str, pid: usize, tid: usize, name: &str) {
+        self.events.push(format!(
+            "{{\"name\":\"{}\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+            kind,
+            pid,
+            tid,
+            escape(name)
+        ));
+    }
+
+    fn slice(
+        &mut self,
+        name: &str,
+        pid: usize,
+        tid: usize,
+        start: Duration,
+        duration: Duration,
+        detail: &str,
+    ) {
+        self.events.push(format!(
+            "{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":{},\"tid\":{},\"ts\":{},\"dur\":{},\"args\":{{\"detail\":\"{}\"}}}}",
+            escape(name),
+            pid,
+            tid,
+            start.as_micros(),
+            duration.as_micros(),
+            escape(detail)
+        ));
+    }
+
+    // Global markers are drawn across all tracks.
+    fn instant(&mut self, name: &str, track: Option<(usize, usize)>, time: Duration, detail: &str) {
+        let (scope, pid, tid) = match track {
+            Some((pid, tid)) => ("t", pid, tid),
+            None => ("g", NODES_PID, 0),
+        };
+        self.events.push(format!(
+            "{{\"name\":\"{}\",\"ph\":\"i\",\"s\":\"{}\",\"pid\":{},\"tid\":{},\"ts\":{},\"args\":{{\"detail\":\"{}\"}}}}",
+            escape(name),
+            scope,
+            pid,
+            tid,
+            time.as_micros(),
+            escape(detail)
+        ));
+    }
+
+    // Flow events bind to the slice enclosing them on the same track.
+    fn flow(&mut self, phase: &str, id: usize, pid: usize, tid: usize, time: Duration) {
+        let mut event = String::new();
+        write!(
+            event,
+            "{{\"name\":\"message\",\"cat\":\"message\",\"ph\":\"{}\",\"id\":{},\"pid\":{},\"tid\":{},\"ts\":{}",
+            phase,
+            id,
+            pid,
+            tid,
+            time.as_micros()
+        )
+        .unwrap();
+        if phase == "f" {
+            event.push_str(",\"bp\":\"e\"");
+        }
+        event.push('}');
+        self.events.push(event);
+    }
+
+    fn link_failure(&mut self, tid: usize, start: Duration, end: Duration, status: LinkStatus) {
+        let name = match status {
+            LinkStatus::Up => unreachable!("Only failures are exported"),
+            LinkStatus::Failed => "failed",
+            LinkStatus::Holding => "holding messages",
+        };
+        self.slice(name, NETWORK_PID, tid, start, end - start, "");
+    }
+
+    fn partition(&mut self, start: Duration, end: Duration, nodes: &[NodeId]) {
+        let nodes = nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        self.slice(
+            "partition",
+            NETWORK_PID,
+            PARTITION_TID,
+            start,
+            end - start,
+            &format!("{} isolated", nodes.join(", ")),
+        );
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
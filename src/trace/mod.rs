@@ -0,0 +1,120 @@
+mod chrome;
+mod sequence;
+
+pub use sequence::DiagramFormat;
+
+use std::{fmt, time::Duration};
+
+use crate::node::NodeId;
+
+/// Why a message was not delivered.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DropReason {
+    /// The link between sender and receiver was down.
+    LinkFailure,
+    /// Sender and receiver were partitioned.
+    Partition,
+    /// A link queue was full.
+    Overflow,
+    /// The receiver was crashed or decommissioned when the message arrived,
+    /// or does not exist (yet).
+    NodeDown,
+    /// A [`crate::Rule`] dropped the message.
+    Rule,
+}
+
+impl fmt::Display for DropReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DropReason::LinkFailure => write!(f, "link failure"),
+            DropReason::Partition => write!(f, "partition"),
+            DropReason::Overflow => write!(f, "queue overflow"),
+            DropReason::NodeDown => write!(f, "node down"),
+            DropReason::Rule => write!(f, "rule"),
+        }
+    }
+}
+
+/// Whether a link delivers, loses or holds messages.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LinkStatus {
+    Up,
+    /// Messages sent on the link are lost.
+    Failed,
+    /// Messages sent on the link are held until it recovers.
+    Holding,
+}
+
+/// Something that happened during a simulation run.
+///
+/// Link and partition changes are only noticed when a message is sent, so they
+/// are recorded at the time of the first send that observes them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent<M> {
+    /// A message was handed to the network. Duplicates share the id of the
+    /// original message.
+    Send {
+        message_id: usize,
+        message: M,
+    },
+    /// A message was processed by its receiver.
+    Deliver {
+        message_id: usize,
+        message: M,
+    },
+    /// The network duplicated a message.
+    Duplicate {
+        message: M,
+    },
+    /// A message was lost.
+    Drop {
+        message: M,
+        reason: DropReason,
+    },
+    NodeCrash(NodeId),
+    NodeRecover(NodeId),
+    LinkChange {
+        from: NodeId,
+        to: NodeId,
+        status: LinkStatus,
+    },
+    /// The given nodes were partitioned from the rest.
+    PartitionStart(Vec<NodeId>),
+    PartitionEnd,
+    /// The invariants were checked (and held).
+    InvariantCheck,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry<M> {
+    /// Simulated time of the event.
+    pub time: Duration,
+    pub event: TraceEvent<M>,
+}
+
+/// Events of a simulation run in the order they happened. Recording is enabled
+/// with [`crate::Simulator::record_trace`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace<M> {
+    entries: Vec<TraceEntry<M>>,
+}
+
+impl<M> Default for Trace<M> {
+    fn default() -> Self {
+        Trace { entries: vec![] }
+    }
+}
+
+impl<M> Trace<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, time: Duration, event: TraceEvent<M>) {
+        self.entries.push(TraceEntry { time, event });
+    }
+
+    pub fn entries(&self) -> &[TraceEntry<M>] {
+        &self.entries
+    }
+}
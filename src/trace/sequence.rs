@@ -0,0 +1,225 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+    ops::Range,
+    time::Duration,
+};
+
+use crate::{node::NodeId, ProtocolMessage};
+
+use super::{LinkStatus, Trace, TraceEvent};
+
+/// Output format of [`Trace::sequence_diagram`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DiagramFormat {
+    Mermaid,
+    PlantUml,
+}
+
+impl<M: ProtocolMessage> Trace<M> {
+    /// Renders the trace as a sequence diagram with one lifeline per node and
+    /// client. Messages are drawn as arrows when they are delivered (labelled
+    /// with send and delivery time) or dropped. Crashes, link changes and
+    /// partitions are drawn as notes. If a window is given, only events in it
+    /// are included.
+    pub fn sequence_diagram(
+        &self,
+        format: DiagramFormat,
+        window: Option<Range<Duration>>,
+    ) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| window.as_ref().is_none_or(|w| w.contains(&entry.time)))
+            .collect::<Vec<_>>();
+
+        let send_times: HashMap<usize, Duration> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                TraceEvent::Send { message_id, .. } => Some((*message_id, entry.time)),
+                _ => None,
+            })
+            .collect();
+
+        let mut participants = BTreeSet::new();
+        for entry in &entries {
+            match &entry.event {
+                TraceEvent::Send { message, .. }
+                | TraceEvent::Deliver { message, .. }
+                | TraceEvent::Duplicate { message }
+                | TraceEvent::Drop { message, .. } => {
+                    participants.insert(message.source());
+                    participants.insert(message.destination());
+                }
+                TraceEvent::NodeCrash(node) | TraceEvent::NodeRecover(node) => {
+                    participants.insert(*node);
+                }
+                TraceEvent::LinkChange { from, to, .. } => {
+                    participants.insert(*from);
+                    participants.insert(*to);
+                }
+                TraceEvent::PartitionStart(nodes) => participants.extend(nodes),
+                TraceEvent::PartitionEnd | TraceEvent::InvariantCheck => {}
+            }
+        }
+
+        let mut diagram = Diagram {
+            format,
+            output: String::new(),
+        };
+        diagram.header(&participants);
+        for entry in entries {
+            let time = entry.time;
+            match &entry.event {
+                // Arrows are drawn once the fate of a message is known.
+                TraceEvent::Send { .. } | TraceEvent::InvariantCheck => {}
+                TraceEvent::Duplicate { message } => {
+                    diagram.note(
+                        &[message.source()],
+                        &format!("[{:?}] {:?} duplicated", time, message),
+                    );
+                }
+                TraceEvent::Deliver {
+                    message_id,
+                    message,
+                } => {
+                    let sent = send_times.get(message_id).copied().unwrap_or(time);
+                    diagram.arrow(
+                        message,
+                        false,
+                        &format!("[{:?} -> {:?}] m{} {:?}", sent, time, message_id, message),
+                    );
+                }
+                TraceEvent::Drop { message, reason } => {
+                    diagram.arrow(
+                        message,
+                        true,
+                        &format!("[{:?}] {:?} dropped ({})", time, message, reason),
+                    );
+                }
+                TraceEvent::NodeCrash(node) => {
+                    diagram.note(&[*node], &format!("[{:?}] crashed", time));
+                }
+                TraceEvent::NodeRecover(node) => {
+                    diagram.note(&[*node], &format!("[{:?}] recovered", time));
+                }
+                TraceEvent::LinkChange { from, to, status } => {
+                    let description = match status {
+                        LinkStatus::Up => "link up",
+                        LinkStatus::Failed => "link failed",
+                        LinkStatus::Holding => "link holds messages",
+                    };
+                    diagram.note(&[*from, *to], &format!("[{:?}] {}", time, description));
+                }
+                TraceEvent::PartitionStart(nodes) => {
+                    let nodes = nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                    diagram.note_across(
+                        &participants,
+                        &format!("[{:?}] partition: {} isolated", time, nodes.join(", ")),
+                    );
+                }
+                TraceEvent::PartitionEnd => {
+                    diagram.note_across(&participants, &format!("[{:?}] partition healed", time));
+                }
+            }
+        }
+        diagram.footer();
+        diagram.output
+    }
+}
+
+struct Diagram {
+    format: DiagramFormat,
+    output: String,
+}
+
+impl Diagram {
+    fn header(&mut self, participants: &BTreeSet<NodeId>) {
+        match self.format {
+            DiagramFormat::Mermaid => self.line("sequenceDiagram"),
+            DiagramFormat::PlantUml => self.line("@startuml"),
+        }
+        for participant in participants {
+            let line = match self.format {
+                DiagramFormat::Mermaid => {
+                    format!("    participant {} as {}", alias(*participant), participant)
+                }
+                DiagramFormat::PlantUml => {
+                    format!("participant \"{}\" as {}", participant, alias(*participant))
+                }
+            };
+            self.line(&line);
+        }
+    }
+
+    fn footer(&mut self) {
+        if self.format == DiagramFormat::PlantUml {
+            self.line("@enduml");
+        }
+    }
+
+    fn arrow<M: ProtocolMessage>(&mut self, message: &M, lost: bool, label: &str) {
+        let from = alias(message.source());
+        let to = alias(message.destination());
+        let line = match (self.format, lost) {
+            (DiagramFormat::Mermaid, false) => format!("    {}->>{}: {}", from, to, escape(label)),
+            (DiagramFormat::Mermaid, true) => format!("    {}-x{}: {}", from, to, escape(label)),
+            (DiagramFormat::PlantUml, false) => format!("{} -> {} : {}", from, to, label),
+            (DiagramFormat::PlantUml, true) => format!("{} ->x {} : {}", from, to, label),
+        };
+        self.line(&line);
+    }
+
+    fn note(&mut self, nodes: &[NodeId], text: &str) {
+        let nodes = nodes.iter().map(|n| alias(*n)).collect::<Vec<_>>();
+        let line = match self.format {
+            DiagramFormat::Mermaid => {
+                format!("    Note over {}: {}", nodes.join(","), escape(text))
+            }
+            DiagramFormat::PlantUml => format!("note over {} : {}", nodes.join(", "), text),
+        };
+        self.line(&line);
+    }
+
+    fn note_across(&mut self, participants: &BTreeSet<NodeId>, text: &str) {
+        match self.format {
+            // Mermaid has no notes across all lifelines, so the note spans
+            // from the first to the last one.
+            DiagramFormat::Mermaid => {
+                let (Some(first), Some(last)) = (participants.first(), participants.last()) else {
+                    return;
+                };
+                if first == last {
+                    self.note(&[*first], text);
+                } else {
+                    self.note(&[*first, *last], text);
+                }
+            }
+            DiagramFormat::PlantUml => self.line(&format!("note across : {}", text)),
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.output, "{}", line).unwrap();
+    }
+}
+
+// Mermaid participant names must not contain parentheses.
+fn alias(id: NodeId) -> String {
+    match id {
+        NodeId::Node(id) => format!("N{}", id),
+        NodeId::Client(id) => format!("C{}", id),
+    }
+}
+
+// `#` starts an entity code and `;` ends a statement in Mermaid.
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '#' => "#35;".to_string(),
+            ';' => "#59;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
@@ -1,4 +1,7 @@
-use std::time::{Duration, Instant};
+use std::{
+    any::Any,
+    time::{Duration, Instant},
+};
 
 use rand::RngCore;
 use rand_distr::{Distribution, Exp};
@@ -12,3 +15,12 @@ pub(crate) fn sample_failure_time(
 
     start_time + Duration::from_secs_f64(mult)
 }
+
+/// Returns the message of a caught panic, e.g., an invariant violation.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown violation")
+}
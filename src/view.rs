@@ -0,0 +1,177 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    node::NodeId, scheduler::EventQueue, Client, DeterministicClient, DeterministicNode, Event,
+    LinkState, Network, Node, ProtocolMessage,
+};
+
+/// A message on its way to its receiver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingDelivery<'a, M> {
+    /// Simulated time at which the message is due.
+    pub time: Duration,
+    pub message: &'a M,
+}
+
+// Where the network state comes from. The model checker has no link model, its
+// messages are just in flight.
+enum NetworkSource<'a, M: ProtocolMessage> {
+    Simulation {
+        start_time: Instant,
+        events: &'a EventQueue<M>,
+        network: &'a Network<M>,
+    },
+    ModelChecker {
+        in_flight: &'a [M],
+    },
+}
+
+/// Read-only state of a simulation passed to invariant checkers, see
+/// [`crate::InvariantChecker::check_view`]. Besides nodes and clients, it
+/// exposes the messages in flight and the fault state of the network.
+///
+/// Links and partitions change state lazily when a message is sent, so their
+/// state is the one seen by the last message sent. The model checker has no
+/// link or partition model: links are never reported and the network is never
+/// partitioned.
+pub struct SimulationView<'a, N: DeterministicNode, C: DeterministicClient<Message = N::Message>> {
+    seed: u64,
+    time: Duration,
+    nodes: &'a [Node<N>],
+    clients: &'a [Client<C>],
+    network: NetworkSource<'a, N::Message>,
+}
+
+impl<'a, N: DeterministicNode, C: DeterministicClient<Message = N::Message>>
+    SimulationView<'a, N, C>
+{
+    pub(crate) fn new(
+        seed: u64,
+        start_time: Instant,
+        time: Duration,
+        nodes: &'a [Node<N>],
+        clients: &'a [Client<C>],
+        events: &'a EventQueue<N::Message>,
+        network: &'a Network<N::Message>,
+    ) -> Self {
+        SimulationView {
+            seed,
+            time,
+            nodes,
+            clients,
+            network: NetworkSource::Simulation {
+                start_time,
+                events,
+                network,
+            },
+        }
+    }
+
+    pub(crate) fn for_model_checker(
+        time: Duration,
+        nodes: &'a [Node<N>],
+        clients: &'a [Client<C>],
+        in_flight: &'a [N::Message],
+    ) -> Self {
+        SimulationView {
+            seed: 0,
+            time,
+            nodes,
+            clients,
+            network: NetworkSource::ModelChecker { in_flight },
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the current simulated time.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn nodes(&self) -> &'a [Node<N>] {
+        self.nodes
+    }
+
+    pub fn clients(&self) -> &'a [Client<C>] {
+        self.clients
+    }
+
+    /// Returns whether the node or client is running, i.e., it is neither
+    /// crashed, recovering nor removed from the simulation.
+    pub fn is_up(&self, id: NodeId) -> bool {
+        match id {
+            NodeId::Node(index) => self.nodes[index].is_up(),
+            NodeId::Client(index) => self.clients[index].is_up(),
+        }
+    }
+
+    /// Returns the messages that have been sent but not delivered yet, in the
+    /// order they are due. Messages a node produced while it is still busy
+    /// processing are not sent yet. In the model checker, all messages in
+    /// flight are due now.
+    pub fn pending_deliveries(&self) -> Vec<PendingDelivery<'a, N::Message>> {
+        match self.network {
+            NetworkSource::Simulation {
+                start_time,
+                events,
+                network,
+            } => events
+                .iter()
+                .filter_map(|(event_time, event)| match event {
+                    Event::Message(msg) if !network.is_delivery_cancelled(msg.delivery_id()) => {
+                        Some(PendingDelivery {
+                            time: event_time.time.duration_since(start_time),
+                            message: msg.message(),
+                        })
+                    }
+                    _ => None,
+                })
+                .collect(),
+            NetworkSource::ModelChecker { in_flight } => in_flight
+                .iter()
+                .map(|message| PendingDelivery {
+                    time: self.time,
+                    message,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the state of the link between the two nodes, or nothing if no
+    /// message was sent on it yet (links start up).
+    pub fn link_state(&self, a: NodeId, b: NodeId) -> Option<&'a LinkState<N::Message>> {
+        match self.network {
+            NetworkSource::Simulation { network, .. } => network.link_state(a, b),
+            NetworkSource::ModelChecker { .. } => None,
+        }
+    }
+
+    /// Returns the states of all links that carried messages, ordered by their
+    /// endpoints. Links are bidirectional, the smaller id comes first.
+    pub fn links(&self) -> Vec<(NodeId, NodeId, &'a LinkState<N::Message>)> {
+        match self.network {
+            NetworkSource::Simulation { network, .. } => network.links(),
+            NetworkSource::ModelChecker { .. } => vec![],
+        }
+    }
+
+    /// Returns both sides of the current partition, or nothing if the network
+    /// is not partitioned.
+    pub fn partitions(&self) -> Vec<Vec<NodeId>> {
+        match self.network {
+            NetworkSource::Simulation { network, .. } => network.partitions(),
+            NetworkSource::ModelChecker { .. } => vec![],
+        }
+    }
+
+    /// Returns whether messages between the two nodes are dropped by the
+    /// current partition.
+    pub fn is_partitioned(&self, a: NodeId, b: NodeId) -> bool {
+        self.partitions()
+            .iter()
+            .any(|side| side.contains(&a) != side.contains(&b))
+    }
+}
@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    node::NodeId, util::sample_failure_time, DeterministicClient, History, LatencyHistogram,
+    OperationId, RequestMark,
+};
+
+use super::{Arrivals, OperationMix, Reply, Workload, WorkloadConfig};
+
+#[derive(Debug, Clone)]
+struct PendingRequest<Op> {
+    op: Op,
+    operation: OperationId,
+    invoked_at: Instant,
+    attempt: u32,
+    // Whether the request was sent and awaits a reply (until the deadline) or
+    // waits for the backoff to send the next attempt (at the deadline).
+    in_flight: bool,
+    deadline: Instant,
+}
+
+/// A client that issues operations of a [`Workload`] according to a
+/// [`WorkloadConfig`]. Requests that are not answered within the timeout are
+/// retried with backoff. All operations are recorded in a [`History`] (e.g.,
+/// for [`crate::check_linearizability`]) and the latencies of completed
+/// operations, from the first attempt to the reply, in a
+/// [`LatencyHistogram`]. The operations are also marked as requests for
+/// [`crate::Simulator::statistics`].
+///
+/// If the client crashes, the outcome of its pending operations stays unknown
+/// and it continues with new operations after recovering.
+#[derive(Debug, Clone)]
+pub struct WorkloadClient<W: Workload> {
+    id: NodeId,
+    workload: W,
+    mix: OperationMix<W::Op>,
+    config: WorkloadConfig,
+    rng: ChaCha8Rng,
+    issued: u64,
+    next_arrival: Option<Instant>,
+    pending: BTreeMap<u64, PendingRequest<W::Op>>,
+    history: History<W::Op, W::Ret>,
+    latencies: LatencyHistogram,
+    marks: Vec<RequestMark>,
+    retries: u64,
+    abandoned: u64,
+}
+
+impl<W: Workload> WorkloadClient<W> {
+    pub fn new(id: NodeId, workload: W, mix: OperationMix<W::Op>, config: WorkloadConfig) -> Self {
+        // Clients with the same seed still make different choices.
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        if let NodeId::Client(index) = id {
+            rng.set_stream(index as u64);
+        }
+
+        WorkloadClient {
+            id,
+            workload,
+            mix,
+            config,
+            rng,
+            issued: 0,
+            next_arrival: None,
+            pending: BTreeMap::new(),
+            history: History::new(),
+            latencies: LatencyHistogram::new(),
+            marks: vec![],
+            retries: 0,
+            abandoned: 0,
+        }
+    }
+
+    pub fn workload(&self) -> &W {
+        &self.workload
+    }
+
+    /// Returns all operations issued so far with their outcomes.
+    pub fn history(&self) -> &History<W::Op, W::Ret> {
+        &self.history
+    }
+
+    /// Returns the latencies of the operations that completed successfully.
+    pub fn latencies(&self) -> &LatencyHistogram {
+        &self.latencies
+    }
+
+    /// Returns the number of requests that were sent again.
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    /// Returns the number of operations that were given up after the maximum
+    /// number of retries or because the client crashed.
+    pub fn abandoned(&self) -> u64 {
+        self.abandoned
+    }
+
+    // Issues new operations as the arrival model allows.
+    fn issue_operations(&mut self, now: Instant, messages: &mut Vec<W::Message>) {
+        match self.config.arrivals {
+            Arrivals::ClosedLoop { concurrency } => {
+                while self.pending.len() < concurrency && self.issued < self.config.total_operations
+                {
+                    self.invoke(now, messages);
+                }
+            }
+            Arrivals::OpenLoop {
+                mean_interarrival_time,
+            } => {
+                let mut next_arrival = match self.next_arrival {
+                    Some(next_arrival) => next_arrival,
+                    None => sample_failure_time(now, mean_interarrival_time, &mut self.rng),
+                };
+                while next_arrival <= now && self.issued < self.config.total_operations {
+                    self.invoke(now, messages);
+                    next_arrival =
+                        sample_failure_time(next_arrival, mean_interarrival_time, &mut self.rng);
+                }
+                self.next_arrival = Some(next_arrival);
+            }
+        }
+    }
+
+    fn invoke(&mut self, now: Instant, messages: &mut Vec<W::Message>) {
+        let op = self.mix.sample(&mut self.rng);
+        self.issued += 1;
+        let request_id = self.issued;
+        let operation = self.history.invoke(self.id, op.clone(), now);
+        messages.push(self.workload.encode(self.id, request_id, 0, &op));
+        self.marks.push(RequestMark::Start(request_id));
+        self.pending.insert(
+            request_id,
+            PendingRequest {
+                op,
+                operation,
+                invoked_at: now,
+                attempt: 0,
+                in_flight: true,
+                deadline: now + self.config.timeout,
+            },
+        );
+    }
+
+    // Waits for the backoff before the next attempt, or gives up the operation
+    // if it was retried too often.
+    fn back_off(&mut self, request_id: u64, now: Instant) {
+        let request = self.pending.get_mut(&request_id).unwrap();
+        if request.attempt >= self.config.max_retries {
+            self.pending.remove(&request_id);
+            self.abandoned += 1;
+            self.marks.push(RequestMark::Cancel(request_id));
+            return;
+        }
+        request.in_flight = false;
+        request.deadline = now + self.config.backoff.delay(request.attempt);
+    }
+
+    // Sends the next attempt of requests whose backoff is over.
+    fn retry(&mut self, request_id: u64, now: Instant, messages: &mut Vec<W::Message>) {
+        let request = self.pending.get_mut(&request_id).unwrap();
+        request.attempt += 1;
+        request.in_flight = true;
+        request.deadline = now + self.config.timeout;
+        self.retries += 1;
+        messages.push(
+            self.workload
+                .encode(self.id, request_id, request.attempt, &request.op),
+        );
+    }
+}
+
+impl<W: Workload> DeterministicClient for WorkloadClient<W> {
+    type Message = W::Message;
+
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn tick(&mut self, now: Instant) -> Vec<Self::Message> {
+        let mut messages = vec![];
+
+        let due = self
+            .pending
+            .iter()
+            .filter(|(_, request)| request.deadline <= now)
+            .map(|(request_id, _)| *request_id)
+            .collect::<Vec<_>>();
+        for request_id in due {
+            if self.pending[&request_id].in_flight {
+                self.back_off(request_id, now);
+            }
+            if self
+                .pending
+                .get(&request_id)
+                .is_some_and(|request| !request.in_flight && request.deadline <= now)
+            {
+                self.retry(request_id, now, &mut messages);
+            }
+        }
+
+        self.issue_operations(now, &mut messages);
+        messages
+    }
+
+    fn process_message(&mut self, msg: Self::Message, now: Instant) -> Vec<Self::Message> {
+        // Replies to abandoned operations and duplicate replies are ignored.
+        let Some((request_id, reply)) = self.workload.decode(&msg) else {
+            return vec![];
+        };
+        let Some(request) = self.pending.get(&request_id) else {
+            return vec![];
+        };
+
+        match reply {
+            Reply::Ok(ret) => {
+                self.history.complete(request.operation, ret, now);
+                self.latencies
+                    .record(now.duration_since(request.invoked_at));
+                self.marks.push(RequestMark::End(request_id));
+                self.pending.remove(&request_id);
+            }
+            Reply::Fail => {
+                self.history.fail(request.operation, now);
+                self.pending.remove(&request_id);
+                self.marks.push(RequestMark::Cancel(request_id));
+            }
+            Reply::Retry if request.in_flight => self.back_off(request_id, now),
+            Reply::Retry => {}
+        }
+
+        let mut messages = vec![];
+        self.issue_operations(now, &mut messages);
+        messages
+    }
+
+    fn finished(&self) -> bool {
+        self.issued == self.config.total_operations && self.pending.is_empty()
+    }
+
+    fn request_marks(&mut self) -> Vec<RequestMark> {
+        std::mem::take(&mut self.marks)
+    }
+
+    fn recover(&mut self, _now: Instant, _nonce: u64) {
+        self.abandoned += self.pending.len() as u64;
+        self.pending.clear();
+        self.next_arrival = None;
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+/// When a [`super::WorkloadClient`] issues new operations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrivals {
+    /// Keeps the given number of operations outstanding. A new operation is
+    /// issued as soon as one completes.
+    ClosedLoop { concurrency: usize },
+    /// Issues operations at random times (a Poisson process with the given
+    /// mean time between operations), regardless of how many are
+    /// outstanding. Operations are sent with the next tick after they arrive.
+    OpenLoop { mean_interarrival_time: Duration },
+}
+
+/// Exponential backoff between retries: the n-th retry (starting at 0) waits
+/// `initial * multiplier^n`, but at most `max` and at least 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub multiplier: f64,
+    pub max: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let delay = self.initial.as_secs_f64() * self.multiplier.powi(retry as i32);
+        // `min` also replaces NaN, e.g., a zero initial delay times an infinite
+        // multiplier.
+        Duration::from_secs_f64(delay.min(self.max.as_secs_f64()).max(0.0))
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(100),
+            multiplier: 2.0,
+            max: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkloadConfig {
+    pub arrivals: Arrivals,
+    /// Number of operations after which the client is finished.
+    pub total_operations: u64,
+    /// A request is retried if no reply arrives within the timeout.
+    pub timeout: Duration,
+    /// The operation is given up after this many retries. Its outcome stays
+    /// unknown, i.e., it may or may not have taken effect.
+    pub max_retries: u32,
+    pub backoff: Backoff,
+    /// Seed of the client's random choices (operations and arrival times).
+    pub seed: u64,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        WorkloadConfig {
+            arrivals: Arrivals::ClosedLoop { concurrency: 1 },
+            total_operations: 100,
+            timeout: Duration::from_millis(500),
+            max_retries: 10,
+            backoff: Backoff::default(),
+            seed: 1,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{fmt, rc::Rc};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, RngCore};
+
+type Generator<Op> = Rc<dyn Fn(&mut dyn RngCore) -> Op>;
+
+/// Weighted choice between kinds of operations, e.g., 90% reads and 10%
+/// writes: `OperationMix::new().with(9, |_| Read).with(1, |rng| Write(rng.gen()))`.
+pub struct OperationMix<Op> {
+    weights: Vec<u32>,
+    generators: Vec<Generator<Op>>,
+}
+
+impl<Op> OperationMix<Op> {
+    pub fn new() -> Self {
+        OperationMix {
+            weights: vec![],
+            generators: vec![],
+        }
+    }
+
+    /// Adds a kind of operation, generated by the given function, that is
+    /// chosen with a probability proportional to its weight.
+    pub fn with(
+        mut self,
+        weight: u32,
+        generate: impl Fn(&mut dyn RngCore) -> Op + 'static,
+    ) -> Self {
+        self.weights.push(weight);
+        self.generators.push(Rc::new(generate));
+        self
+    }
+
+    /// Generates the next operation. Panics if the mix is empty or all
+    /// weights are 0.
+    pub(crate) fn sample(&self, rand: &mut dyn RngCore) -> Op {
+        let index = WeightedIndex::new(&self.weights)
+            .expect("Operation mix needs an operation with positive weight")
+            .sample(rand);
+        (self.generators[index])(rand)
+    }
+}
+
+impl<Op> Default for OperationMix<Op> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Op> Clone for OperationMix<Op> {
+    fn clone(&self) -> Self {
+        OperationMix {
+            weights: self.weights.clone(),
+            generators: self.generators.clone(),
+        }
+    }
+}
+
+impl<Op> fmt::Debug for OperationMix<Op> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OperationMix")
+            .field("weights", &self.weights)
+            .finish()
+    }
+}
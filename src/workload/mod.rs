@@ -0,0 +1,42 @@
+mod client;
+mod config;
+mod mix;
+
+pub use client::WorkloadClient;
+pub use config::{Arrivals, Backoff, WorkloadConfig};
+pub use mix::OperationMix;
+
+use std::fmt::Debug;
+
+use crate::{node::NodeId, ProtocolMessage};
+
+/// How a protocol's clients talk to the system. A [`WorkloadClient`] generates
+/// the operations, keeps track of pending requests, timeouts and retries, so a
+/// workload only translates operations into requests and replies into results.
+pub trait Workload: Debug {
+    type Message: ProtocolMessage;
+    type Op: Clone + Debug;
+    type Ret: Clone + Debug;
+
+    /// Returns the request for the operation. The request id identifies the
+    /// operation across retries, the attempt counts the retries (starting at
+    /// 0), e.g., to send a retry to a different node.
+    fn encode(&self, client: NodeId, request_id: u64, attempt: u32, op: &Self::Op)
+        -> Self::Message;
+
+    /// Returns the id of the request the message replies to and the reply, or
+    /// nothing if the message is not a reply.
+    fn decode(&self, message: &Self::Message) -> Option<(u64, Reply<Self::Ret>)>;
+}
+
+/// Reply to a request.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Reply<Ret> {
+    /// The operation completed with the given result.
+    Ok(Ret),
+    /// The operation definitely did not take effect and is not retried.
+    Fail,
+    /// The request should be retried after a backoff, e.g., because it was
+    /// sent to a node that is not the leader.
+    Retry,
+}